@@ -0,0 +1,100 @@
+use std::fmt::Display;
+
+use fractl_lib::Scene;
+
+// Named scenes, stored as a `[name]` header followed by the lines of the scene
+#[derive(Default)]
+pub struct Bookmarks {
+    entries: Vec<(String, Scene)>,
+}
+
+impl Bookmarks {
+    #[cfg(not(target_arch = "wasm32"))]
+    const PATH: &'static str = "fractl_bookmarks.txt";
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut entries = Vec::new();
+        let mut current: Option<(String, String)> = None;
+
+        for line in text.lines() {
+            if let Some(name) = line.trim().strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                if let Some((name, scene)) = current.take() {
+                    entries.push((name, Scene::parse(&scene)?));
+                }
+
+                current = Some((name.to_string(), String::new()));
+            } else if let Some((_, scene)) = &mut current {
+                scene.push_str(line);
+                scene.push('\n');
+            } else if !line.trim().is_empty() && !line.trim().starts_with('#') {
+                return Err("bookmarks must start with a [name] header");
+            }
+        }
+
+        if let Some((name, scene)) = current {
+            entries.push((name, Scene::parse(&scene)?));
+        }
+
+        Ok(Self { entries })
+    }
+
+    // Missing file means no bookmarks yet
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Result<Self, &'static str> {
+        match std::fs::read_to_string(Self::PATH) {
+            Ok(text) => Self::parse(&text),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(_) => Err("failed to read the bookmarks file"),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), &'static str> {
+        std::fs::write(Self::PATH, self.to_string()).map_err(|_| "failed to write the bookmarks file")
+    }
+
+    // There is no file system on the web, bookmarks only last until the page is closed
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Result<Self, &'static str> {
+        Ok(Self::default())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&(String, Scene)> {
+        self.entries.get(index)
+    }
+
+    // Saving under an existing name replaces that bookmark, returns the index of the bookmark
+    pub fn insert(&mut self, name: String, scene: Scene) -> usize {
+        if let Some(index) = self.entries.iter().position(|(entry_name, _)| *entry_name == name) {
+            self.entries[index].1 = scene;
+            index
+        } else {
+            self.entries.push((name, scene));
+            self.entries.len() - 1
+        }
+    }
+}
+
+impl Display for Bookmarks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (n, (name, scene)) in self.entries.iter().enumerate() {
+            if n > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "[{name}]")?;
+            write!(f, "{scene}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::too_many_lines)]
 
+mod bookmarks;
 mod state;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -9,6 +10,7 @@ use std::{num::NonZeroU32, rc::Rc};
 
 use cfg_if::cfg_if;
 use cgmath::Vector2;
+use fractl_lib::{Formula, FractalType, LyapunovSequence};
 use winit::{
     event::{ElementState, Event, KeyEvent, MouseScrollDelta, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
@@ -71,6 +73,30 @@ fn main() {
     };
     let mut state = State::new(screen_size);
 
+    // Either a custom formula, a Lyapunov sequence or an image saved with Ctrl + S
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(argument) = std::env::args().nth(1) {
+        if std::path::Path::new(&argument)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+        {
+            if let Err(error) = state.load_png(std::path::Path::new(&argument)) {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        } else if let Ok(sequence) = LyapunovSequence::new(&argument) {
+            state.set_fractal_type(FractalType::Lyapunov(sequence));
+        } else {
+            match Formula::new(&argument) {
+                Ok(formula) => state.set_fractal_type(FractalType::Custom(formula)),
+                Err(error) => {
+                    eprintln!("{error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
     event_loop
         .run(move |event, elwt| {
             elwt.set_control_flow(ControlFlow::Wait);
@@ -103,15 +129,16 @@ fn main() {
                 Event::WindowEvent { window_id, event } => {
                     if window_id == window.id() {
                         match event {
-                            WindowEvent::CloseRequested
-                            | WindowEvent::KeyboardInput {
+                            WindowEvent::CloseRequested => elwt.exit(),
+                            // Escape cancels typing in a bookmark name instead
+                            WindowEvent::KeyboardInput {
                                 event:
                                     KeyEvent {
                                         logical_key: Key::Named(NamedKey::Escape),
                                         ..
                                     },
                                 ..
-                            } => {
+                            } if !state.is_naming_bookmark() => {
                                 elwt.exit();
                             }
                             WindowEvent::KeyboardInput {
@@ -134,15 +161,40 @@ fn main() {
                                     window.request_redraw();
                                 }
                             }
+                            #[cfg(not(target_arch = "wasm32"))]
+                            WindowEvent::DroppedFile(path) => match state.load_png(&path) {
+                                Ok(()) => window.request_redraw(),
+                                Err(error) => eprintln!("{error}"),
+                            },
                             WindowEvent::CursorMoved { position, .. } => {
                                 mouse_pos.x = position.x;
                                 mouse_pos.y = position.y;
+
+                                if state.handle_cursor_moved(Some(mouse_pos), screen_size) {
+                                    window.request_redraw();
+                                }
                             }
-                            WindowEvent::MouseWheel {
-                                delta: MouseScrollDelta::LineDelta(x, y),
-                                ..
-                            } => {
-                                if state.handle_mousewheel_input(Vector2::new(x, y)) {
+                            WindowEvent::ModifiersChanged(modifiers) => {
+                                state.handle_modifiers_changed(modifiers.state());
+                            }
+                            WindowEvent::CursorLeft { .. } => {
+                                if state.handle_cursor_moved(None, screen_size) {
+                                    window.request_redraw();
+                                }
+                            }
+                            WindowEvent::MouseWheel { delta, .. } => {
+                                // Trackpads scroll by pixels instead of lines
+                                const PIXELS_PER_LINE: f32 = 40.0;
+
+                                #[allow(clippy::cast_possible_truncation)]
+                                let delta = match delta {
+                                    MouseScrollDelta::LineDelta(x, y) => Vector2::new(x, y),
+                                    MouseScrollDelta::PixelDelta(position) => {
+                                        Vector2::new(position.x as f32, position.y as f32) / PIXELS_PER_LINE
+                                    }
+                                };
+
+                                if state.handle_mousewheel_input(delta, mouse_pos, screen_size) {
                                     window.request_redraw();
                                 }
                             }
@@ -1,12 +1,17 @@
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, time::Duration};
 
 use cgmath::Vector2;
-use fractl_lib::{float, Camera, ColorType, Draw, Fill, Float, Fractal, FractalType, FrameBuffer, Label};
+use fractl_lib::{
+    float, Bailout, Camera, ColorType, Draw, Fill, Float, FractalType, FrameBuffer, HeightField, InteriorColorType,
+    Label, OrbitTrap, Relief, Scene,
+};
 use winit::{
     event::{ElementState, KeyEvent, MouseButton},
-    keyboard::{KeyCode, PhysicalKey},
+    keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey},
 };
 
+use crate::bookmarks::Bookmarks;
+
 const DEFAULT_MAX_ITERATIONS: NonZeroU32 = unsafe {
     NonZeroU32::new_unchecked(
         if cfg!(debug_assertions) { 32 } else { 64 }
@@ -21,14 +26,51 @@ const DEFAULT_MAX_ITERATIONS: NonZeroU32 = unsafe {
 };
 const DEFAULT_SHOW_CROSSHAIR: bool = true;
 const DEFAULT_SHOW_UI: bool = true;
+// Cursor movement in pixels before a click turns into a drag
+const DRAG_THRESHOLD: u32 = 3;
+// Changes closer together than this are undone in one step, so a drag or a held key is a single entry
+const HISTORY_COALESCE_TIME: Duration = Duration::from_millis(500);
+const MAX_HISTORY_LEN: usize = 256;
+
+enum Drag {
+    Pan {
+        start_pos: Vector2<u32>,
+        last_pos: Vector2<u32>,
+        moved: bool,
+        // Recorded in the history once the drag ends, so moving the cursor does not have to snapshot the scene
+        start_scene: Box<Scene>,
+    },
+    // Started with shift held, zooms to the selected rectangle
+    ZoomRect {
+        start_pos: Vector2<u32>,
+    },
+}
 
 pub struct State {
     camera: Camera,
     selected_fractal_type: FractalType,
     selected_color_type: ColorType,
     max_iterations: NonZeroU32,
+    bailout: Option<Bailout>,
+    // Only set by loaded scenes, kept so they round-trip through the history and the bookmarks
+    palette_offset: Float,
+    relief: Option<Relief>,
+    orbit_trap: Option<OrbitTrap>,
+    selected_interior_color_type: Option<InteriorColorType>,
     show_crosshair: bool,
     show_ui: bool,
+    cursor_pos: Option<Vector2<u32>>,
+    modifiers: ModifiersState,
+    drag: Option<Drag>,
+    // Last rendered scene, reused while only the UI changes
+    rendered: Option<(Scene, FrameBuffer, Duration)>,
+    history: Vec<Scene>,
+    redo_history: Vec<Scene>,
+    last_change: Option<instant::Instant>,
+    bookmarks: Bookmarks,
+    selected_bookmark: Option<usize>,
+    // Name being typed in while saving a bookmark
+    bookmark_name: Option<String>,
 }
 
 impl State {
@@ -39,30 +81,200 @@ impl State {
             selected_fractal_type: FractalType::default(),
             selected_color_type: ColorType::default(),
             max_iterations: DEFAULT_MAX_ITERATIONS,
+            bailout: None,
+            palette_offset: 0.0,
+            relief: None,
+            orbit_trap: None,
+            selected_interior_color_type: None,
             show_crosshair: DEFAULT_SHOW_CROSSHAIR,
             show_ui: DEFAULT_SHOW_UI,
+            cursor_pos: None,
+            modifiers: ModifiersState::empty(),
+            drag: None,
+            rendered: None,
+            history: Vec::new(),
+            redo_history: Vec::new(),
+            last_change: None,
+            bookmarks: Bookmarks::load().unwrap_or_else(|error| {
+                eprintln!("{error}");
+                Bookmarks::default()
+            }),
+            selected_bookmark: None,
+            bookmark_name: None,
+        }
+    }
+
+    #[allow(clippy::large_types_passed_by_value)]
+    pub fn set_fractal_type(&mut self, fractal_type: FractalType) {
+        self.selected_fractal_type = fractal_type;
+    }
+
+    fn bailout(&self) -> Bailout {
+        self.bailout
+            .unwrap_or_else(|| Bailout::of_formula(&self.selected_fractal_type))
+    }
+
+    fn scene(&self) -> Scene {
+        Scene {
+            camera: self.camera.clone(),
+            fractal_type: self.selected_fractal_type,
+            color_type: self.selected_color_type,
+            max_iterations: self.max_iterations,
+            bailout: self.bailout,
+            palette_offset: self.palette_offset,
+            relief: self.relief.clone(),
+            orbit_trap: self.orbit_trap.clone(),
+            interior_color_type: self.selected_interior_color_type,
+        }
+    }
+
+    // The aspect ratio of the camera gets fixed by the next resize
+    fn set_scene(&mut self, scene: Scene) {
+        self.camera = scene.camera;
+        self.selected_fractal_type = scene.fractal_type;
+        self.selected_color_type = scene.color_type;
+        self.max_iterations = scene.max_iterations;
+        self.bailout = scene.bailout;
+        self.palette_offset = scene.palette_offset;
+        self.relief = scene.relief;
+        self.orbit_trap = scene.orbit_trap;
+        self.selected_interior_color_type = scene.interior_color_type;
+    }
+
+    // Pushes the scene from before a change onto the history, unless it continues the previous change
+    fn record_history(&mut self, previous_scene: Scene) {
+        if self.scene() == previous_scene {
+            return;
+        }
+
+        if self
+            .last_change
+            .map_or(true, |last_change| last_change.elapsed() > HISTORY_COALESCE_TIME)
+        {
+            if self.history.len() == MAX_HISTORY_LEN {
+                self.history.remove(0);
+            }
+            self.history.push(previous_scene);
+        }
+
+        self.redo_history.clear();
+        self.last_change = Some(instant::Instant::now());
+    }
+
+    fn undo(&mut self) -> bool {
+        if let Some(scene) = self.history.pop() {
+            let current_scene = self.scene();
+            self.redo_history.push(current_scene);
+            self.set_scene(scene);
+            self.last_change = None;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn redo(&mut self) -> bool {
+        if let Some(scene) = self.redo_history.pop() {
+            let current_scene = self.scene();
+            self.history.push(current_scene);
+            self.set_scene(scene);
+            self.last_change = None;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    // Jumps are always their own history entry
+    fn jump_to_scene(&mut self, scene: Scene) {
+        let previous_scene = self.scene();
+
+        self.last_change = None;
+        self.set_scene(scene);
+        self.record_history(previous_scene);
+        self.last_change = None;
+    }
+
+    fn select_bookmark(&mut self, index: usize) -> bool {
+        if let Some((_, scene)) = self.bookmarks.get(index) {
+            self.jump_to_scene(scene.clone());
+            self.selected_bookmark = Some(index);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    // Restores the view saved in an image by `save_png`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_png(&mut self, path: &std::path::Path) -> Result<(), &'static str> {
+        let file = std::fs::File::open(path).map_err(|_| "failed to open the image")?;
+        let scene = Scene::read_png(std::io::BufReader::new(file))?;
+
+        self.jump_to_scene(scene);
+        self.selected_bookmark = None;
+
+        Ok(())
+    }
+
+    // Saves the last render without the UI as fractl_000.png, fractl_001.png, ... with the scene inside
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_png(&self) -> Result<(), &'static str> {
+        let (scene, framebuffer, _) = self.rendered.as_ref().ok_or("nothing has been rendered yet")?;
+
+        let path = (0..=u32::MAX)
+            .map(|index| std::path::PathBuf::from(format!("fractl_{index:03}.png")))
+            .find(|path| !path.exists())
+            .ok_or("no free file name for the image")?;
+        let file = std::fs::File::create(&path).map_err(|_| "failed to create the image")?;
+
+        scene.write_png(std::io::BufWriter::new(file), framebuffer)
+    }
+
+    fn save_bookmark(&mut self, name: &str) {
+        let name = match name.trim() {
+            "" => format!("Bookmark {}", self.bookmarks.len() + 1),
+            name => name.to_string(),
+        };
+
+        self.selected_bookmark = Some(self.bookmarks.insert(name, self.scene()));
+
+        if let Err(error) = self.bookmarks.save() {
+            eprintln!("{error}");
         }
     }
 
+    pub fn is_naming_bookmark(&self) -> bool {
+        self.bookmark_name.is_some()
+    }
+
     pub fn resize(&mut self, new_screen_size: impl Into<Vector2<NonZeroU32>>) {
         self.camera.resize(new_screen_size);
     }
 
-    pub fn render(&self, screen_size: impl Into<Vector2<NonZeroU32>>) -> Vec<u32> {
-        let mut framebuffer = FrameBuffer::new(screen_size.into());
+    pub fn render(&mut self, screen_size: impl Into<Vector2<NonZeroU32>>) -> Vec<u32> {
+        let screen_size = screen_size.into();
+        let scene = self.scene();
 
-        let frametime = {
-            let now = instant::Instant::now();
+        let (mut framebuffer, frametime) = match &self.rendered {
+            Some((rendered_scene, framebuffer, frametime))
+                if *rendered_scene == scene && *framebuffer.size() == screen_size =>
+            {
+                (framebuffer.clone(), *frametime)
+            }
+            _ => {
+                let mut framebuffer = FrameBuffer::new(screen_size);
 
-            Fractal::new(
-                self.selected_fractal_type,
-                self.selected_color_type,
-                self.camera.clone(),
-                self.max_iterations,
-            )
-            .fill(&mut framebuffer);
+                let now = instant::Instant::now();
+                scene.fractal().fill(&mut framebuffer);
+                let frametime = now.elapsed();
 
-            now.elapsed()
+                self.rendered = Some((scene, framebuffer.clone(), frametime));
+                (framebuffer, frametime)
+            }
         };
 
         if self.show_crosshair {
@@ -81,6 +293,19 @@ impl State {
             }
         }
 
+        if let (Some(Drag::ZoomRect { start_pos }), Some(cursor_pos)) = (&self.drag, self.cursor_pos) {
+            let (min, max) = (start_pos.zip(cursor_pos, u32::min), start_pos.zip(cursor_pos, u32::max));
+
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    if x == min.x || x == max.x || y == min.y || y == max.y {
+                        let current_pixel = Vector2::new(x, y);
+                        framebuffer[current_pixel] = framebuffer[current_pixel].invert();
+                    }
+                }
+            }
+        }
+
         if self.show_ui {
             let (start_y, line_offset) = (40, 40);
             Label::new("Fractaller", 40.0, None)
@@ -94,9 +319,47 @@ impl State {
                 .unwrap()
                 .draw(Vector2::new(10, start_y + line_offset * 3), &mut framebuffer);
 
+            Label::new(
+                format!(
+                    "Interior coloring: {:}",
+                    self.selected_interior_color_type
+                        .map_or("Same as exterior".to_string(), |interior_color_type| {
+                            interior_color_type.to_string()
+                        })
+                ),
+                25.0,
+                None,
+            )
+            .unwrap()
+            .draw(Vector2::new(10, start_y + line_offset * 4), &mut framebuffer);
+
+            Label::new(
+                format!(
+                    "Relief: {:}",
+                    self.relief
+                        .as_ref()
+                        .map_or("Off".to_string(), |relief| relief.height_field().to_string())
+                ),
+                25.0,
+                None,
+            )
+            .unwrap()
+            .draw(Vector2::new(10, start_y + line_offset * 5), &mut framebuffer);
+
+            Label::new(
+                format!(
+                    "Orbit trap: {:}",
+                    self.orbit_trap.as_ref().map_or("Off".to_string(), ToString::to_string)
+                ),
+                25.0,
+                None,
+            )
+            .unwrap()
+            .draw(Vector2::new(10, start_y + line_offset * 6), &mut framebuffer);
+
             Label::new(format!("Max iterations: {:}", self.max_iterations), 25.0, None)
                 .unwrap()
-                .draw(Vector2::new(10, start_y + line_offset * 4), &mut framebuffer);
+                .draw(Vector2::new(10, start_y + line_offset * 7), &mut framebuffer);
 
             Label::new(
                 format!("Frametime: {:} ms", frametime.as_secs_f32() * 1000.0),
@@ -104,7 +367,7 @@ impl State {
                 None,
             )
             .unwrap()
-            .draw(Vector2::new(10, start_y + line_offset * 5), &mut framebuffer);
+            .draw(Vector2::new(10, start_y + line_offset * 8), &mut framebuffer);
 
             Label::new(
                 format!(
@@ -116,7 +379,7 @@ impl State {
                 None,
             )
             .unwrap()
-            .draw(Vector2::new(10, start_y + line_offset * 6), &mut framebuffer);
+            .draw(Vector2::new(10, start_y + line_offset * 9), &mut framebuffer);
 
             Label::new(
                 format!(
@@ -128,7 +391,57 @@ impl State {
                 None,
             )
             .unwrap()
-            .draw(Vector2::new(10, start_y + line_offset * 7), &mut framebuffer);
+            .draw(Vector2::new(10, start_y + line_offset * 10), &mut framebuffer);
+
+            Label::new(format!("Bailout: {:}", self.bailout()), 25.0, None)
+                .unwrap()
+                .draw(Vector2::new(10, start_y + line_offset * 11), &mut framebuffer);
+
+            Label::new(format!("Projection: {:}", self.camera.projection()), 25.0, None)
+                .unwrap()
+                .draw(Vector2::new(10, start_y + line_offset * 12), &mut framebuffer);
+
+            if let Some(cursor_pos) = self.cursor_pos {
+                let fractal = self.scene().fractal();
+                let world_pos = self.camera.screen_to_world_pos(&cursor_pos, &screen_size);
+                let escape_time =
+                    fractal
+                        .formula()
+                        .escape_time(world_pos, self.max_iterations, fractal.bailout(), None, None);
+
+                Label::new(
+                    format!(
+                        "Cursor pos: ({:}, {:}), escape time: {:}",
+                        world_pos.x,
+                        world_pos.y,
+                        match escape_time.lyapunov_exponent {
+                            Some(lyapunov_exponent) => format!("{lyapunov_exponent:.4} (Lyapunov exponent)"),
+                            None if escape_time.iterations == self.max_iterations.get() => "Inside".to_string(),
+                            None => format!("{:.2}", escape_time.smooth_iterations),
+                        }
+                    ),
+                    25.0,
+                    None,
+                )
+                .unwrap()
+                .draw(Vector2::new(10, start_y + line_offset * 13), &mut framebuffer);
+            }
+
+            let bookmark_text = match (&self.bookmark_name, self.selected_bookmark) {
+                (Some(bookmark_name), _) => Some(format!("Bookmark name: {bookmark_name}_")),
+                (None, Some(index)) => self
+                    .bookmarks
+                    .get(index)
+                    .map(|(name, _)| format!("Bookmark: {name} ({}/{})", index + 1, self.bookmarks.len())),
+                (None, None) if self.bookmarks.len() > 0 => Some(format!("Bookmarks: {} saved", self.bookmarks.len())),
+                (None, None) => None,
+            };
+
+            if let Some(bookmark_text) = bookmark_text {
+                Label::new(bookmark_text, 25.0, None)
+                    .unwrap()
+                    .draw(Vector2::new(10, start_y + line_offset * 14), &mut framebuffer);
+            }
         }
 
         framebuffer.raw()
@@ -137,6 +450,7 @@ impl State {
     fn handle_state_keyboard_input(&mut self, key_event: &KeyEvent) -> bool {
         const CHANGE_MAX_ITERATIONS_MULT: Float = 1.5;
         const CHANGE_MULTIBROT_EXPONENT_STEP: Float = 0.05;
+        const CHANGE_BAILOUT_RADIUS_MULT: Float = 2.0;
 
         if key_event.state == ElementState::Pressed {
             if let PhysicalKey::Code(key_code) = key_event.physical_key {
@@ -182,6 +496,68 @@ impl State {
 
                         true
                     }
+                    KeyCode::KeyG => {
+                        self.relief = match self.relief.as_ref().map(Relief::height_field) {
+                            None => Some(Relief::default()),
+                            Some(HeightField::Distance) => {
+                                let mut relief = Relief::default();
+                                relief.set_height_field(HeightField::SmoothIterations);
+                                Some(relief)
+                            }
+                            Some(HeightField::SmoothIterations) => None,
+                        };
+
+                        true
+                    }
+                    KeyCode::KeyJ => {
+                        self.orbit_trap = match self.orbit_trap {
+                            None => Some(OrbitTrap::Point(Vector2::new(0.0, 0.0))),
+                            Some(OrbitTrap::Point(_)) => Some(OrbitTrap::Line {
+                                point: Vector2::new(0.0, 0.0),
+                                angle: 0.0,
+                            }),
+                            Some(OrbitTrap::Line { .. }) => Some(OrbitTrap::Cross(Vector2::new(0.0, 0.0))),
+                            Some(OrbitTrap::Cross(_)) => Some(OrbitTrap::Circle {
+                                center: Vector2::new(0.0, 0.0),
+                                radius: 0.5,
+                            }),
+                            Some(OrbitTrap::Circle { .. } | OrbitTrap::Image { .. }) => None,
+                        };
+
+                        true
+                    }
+                    KeyCode::KeyI => {
+                        self.selected_interior_color_type = match self.selected_interior_color_type {
+                            None => Some(InteriorColorType::default()),
+                            Some(InteriorColorType::Distance) => None,
+                            Some(interior_color_type) => Some(interior_color_type.next()),
+                        };
+
+                        true
+                    }
+                    // Orbits of convergence fractals like Nova never escape, so there is no bailout to change
+                    KeyCode::KeyH if !self.bailout().is_never() => {
+                        let bailout = self.bailout();
+                        self.bailout = Bailout::new(bailout.radius(), bailout.norm().next()).ok();
+
+                        true
+                    }
+                    KeyCode::BracketRight if !self.bailout().is_never() => {
+                        let bailout = self.bailout();
+                        self.bailout = Bailout::new(bailout.radius() * CHANGE_BAILOUT_RADIUS_MULT, bailout.norm())
+                            .ok()
+                            .or(self.bailout);
+
+                        true
+                    }
+                    KeyCode::BracketLeft if !self.bailout().is_never() => {
+                        let bailout = self.bailout();
+                        self.bailout = Bailout::new(bailout.radius() / CHANGE_BAILOUT_RADIUS_MULT, bailout.norm())
+                            .ok()
+                            .or(self.bailout);
+
+                        true
+                    }
                     KeyCode::KeyY => {
                         self.show_crosshair ^= true;
 
@@ -204,6 +580,18 @@ impl State {
 
                         true
                     }
+                    KeyCode::KeyQ => {
+                        self.selected_fractal_type
+                            .change_multi_parametr_imaginary(-CHANGE_MULTIBROT_EXPONENT_STEP);
+
+                        true
+                    }
+                    KeyCode::KeyE => {
+                        self.selected_fractal_type
+                            .change_multi_parametr_imaginary(CHANGE_MULTIBROT_EXPONENT_STEP);
+
+                        true
+                    }
                     _ => false,
                 }
             } else {
@@ -214,9 +602,27 @@ impl State {
         }
     }
 
-    pub fn handle_mousewheel_input(&mut self, delta: Vector2<f32>) -> bool {
+    // `delta` is in lines, positive zooms in towards the cursor
+    pub fn handle_mousewheel_input(
+        &mut self,
+        delta: Vector2<f32>,
+        mouse_pos: Vector2<f64>,
+        screen_size: impl Into<Vector2<NonZeroU32>>,
+    ) -> bool {
+        const ZOOM_PER_SCROLL_LINE: Float = 1.1;
+
         if delta.y.is_normal() {
-            self.change_zoom(delta.y.is_sign_positive());
+            let previous_scene = self.scene();
+
+            // `into` is a no-op with the f32 feature
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::useless_conversion)]
+            self.camera.zoom_at(
+                ZOOM_PER_SCROLL_LINE.powf(delta.y.into()),
+                &mouse_pos.map(|x| x as u32),
+                &screen_size.into(),
+            );
+            self.record_history(previous_scene);
+
             true
         } else {
             false
@@ -230,31 +636,196 @@ impl State {
         mouse_pos: Vector2<f64>,
         screen_size: impl Into<Vector2<NonZeroU32>>,
     ) -> bool {
-        if matches!(state, ElementState::Pressed) {
-            let screen_size = screen_size.into();
+        const MIN_ZOOM_RECT_SIZE: u32 = 4;
+
+        let screen_size = screen_size.into();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let mouse_pos = mouse_pos.map(|x| x as u32);
+
+        match (button, state) {
+            (MouseButton::Left, ElementState::Pressed) => {
+                self.drag = Some(if self.modifiers.shift_key() {
+                    Drag::ZoomRect { start_pos: mouse_pos }
+                } else {
+                    Drag::Pan {
+                        start_pos: mouse_pos,
+                        last_pos: mouse_pos,
+                        moved: false,
+                        start_scene: Box::new(self.scene()),
+                    }
+                });
 
-            match button {
-                MouseButton::Left => {
-                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-                    let world_pos = self
-                        .camera
-                        .screen_to_world_pos(&mouse_pos.map(|x| x as u32), &screen_size);
-                    self.camera.set_center_pos(world_pos);
+                false
+            }
+            (MouseButton::Left, ElementState::Released) => match self.drag.take() {
+                // A click without dragging centers the view on the cursor
+                Some(Drag::Pan { moved: false, .. }) => {
+                    let previous_scene = self.scene();
+                    let plane_pos = self.camera.screen_to_plane_pos(&mouse_pos, &screen_size);
+                    self.camera.set_center_pos(plane_pos);
+                    self.record_history(previous_scene);
 
                     true
                 }
-                _ => false,
+                Some(Drag::ZoomRect { start_pos }) => {
+                    let rect_size = start_pos.zip(mouse_pos, u32::abs_diff);
+
+                    if rect_size.x >= MIN_ZOOM_RECT_SIZE && rect_size.y >= MIN_ZOOM_RECT_SIZE {
+                        let previous_scene = self.scene();
+                        self.camera.zoom_to_rect(&start_pos, &mouse_pos, &screen_size);
+                        self.record_history(previous_scene);
+                    }
+
+                    true
+                }
+                Some(Drag::Pan {
+                    moved: true,
+                    start_scene,
+                    ..
+                }) => {
+                    self.record_history(*start_scene);
+
+                    false
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn handle_modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    // Returns whether the view or the cursor readout needs a redraw
+    pub fn handle_cursor_moved(
+        &mut self,
+        mouse_pos: Option<Vector2<f64>>,
+        screen_size: impl Into<Vector2<NonZeroU32>>,
+    ) -> bool {
+        let screen_size = screen_size.into();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let cursor_pos = mouse_pos
+            .map(|mouse_pos| mouse_pos.map(|x| x as u32))
+            .filter(|cursor_pos| cursor_pos.x < screen_size.x.get() && cursor_pos.y < screen_size.y.get());
+
+        let changed = cursor_pos != self.cursor_pos;
+        self.cursor_pos = cursor_pos;
+
+        match (&mut self.drag, cursor_pos) {
+            (
+                Some(Drag::Pan {
+                    start_pos,
+                    last_pos,
+                    moved,
+                    ..
+                }),
+                Some(cursor_pos),
+            ) => {
+                let distance = start_pos.zip(cursor_pos, u32::abs_diff);
+                *moved |= distance.x.max(distance.y) > DRAG_THRESHOLD;
+
+                if *moved && *last_pos != cursor_pos {
+                    self.camera.pan(last_pos, &cursor_pos, &screen_size);
+                    *last_pos = cursor_pos;
+
+                    true
+                } else {
+                    false
+                }
             }
-        } else {
-            false
+            (Some(Drag::ZoomRect { .. }), _) => changed,
+            _ => changed && self.show_ui,
         }
     }
 
-    pub fn handle_keyboard_input(&mut self, key_event: &KeyEvent) -> bool {
-        self.handle_state_keyboard_input(key_event) || self.camera.handle_keyboard_input(key_event)
+    fn handle_bookmark_name_input(&mut self, key_event: &KeyEvent) -> bool {
+        if key_event.state != ElementState::Pressed {
+            return false;
+        }
+
+        match &key_event.logical_key {
+            Key::Named(NamedKey::Enter) => {
+                if let Some(name) = self.bookmark_name.take() {
+                    self.save_bookmark(&name);
+                }
+            }
+            Key::Named(NamedKey::Escape) => self.bookmark_name = None,
+            Key::Named(NamedKey::Backspace) => {
+                self.bookmark_name.as_mut().map(String::pop);
+            }
+            _ => {
+                if let (Some(bookmark_name), Some(text)) = (&mut self.bookmark_name, key_event.text.as_deref()) {
+                    // Brackets would be read back as a header in the bookmarks file
+                    bookmark_name.extend(
+                        text.chars()
+                            .filter(|char| !char.is_control() && *char != '[' && *char != ']'),
+                    );
+                }
+            }
+        }
+
+        true
+    }
+
+    // Keys that work on the history and the bookmarks instead of changing the scene directly
+    fn handle_history_keyboard_input(&mut self, key_event: &KeyEvent) -> bool {
+        if key_event.state != ElementState::Pressed {
+            return false;
+        }
+
+        match key_event.physical_key {
+            PhysicalKey::Code(KeyCode::KeyZ) if self.modifiers.control_key() => {
+                if self.modifiers.shift_key() {
+                    self.redo();
+                } else {
+                    self.undo();
+                }
+
+                true
+            }
+            PhysicalKey::Code(KeyCode::KeyB) if self.modifiers.control_key() => {
+                self.bookmark_name = Some(String::new());
+
+                true
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            PhysicalKey::Code(KeyCode::KeyS) if self.modifiers.control_key() => {
+                if let Err(error) = self.save_png() {
+                    eprintln!("{error}");
+                }
+
+                true
+            }
+            PhysicalKey::Code(KeyCode::PageDown) if self.bookmarks.len() > 0 => self.select_bookmark(
+                self.selected_bookmark
+                    .map_or(0, |index| (index + 1) % self.bookmarks.len()),
+            ),
+            PhysicalKey::Code(KeyCode::PageUp) if self.bookmarks.len() > 0 => self.select_bookmark(
+                (self
+                    .selected_bookmark
+                    .map_or(self.bookmarks.len(), |index| index + self.bookmarks.len())
+                    - 1)
+                    % self.bookmarks.len(),
+            ),
+            _ => false,
+        }
     }
 
-    pub fn change_zoom(&mut self, increase: bool) {
-        self.camera.change_zoom(increase);
+    pub fn handle_keyboard_input(&mut self, key_event: &KeyEvent) -> bool {
+        if self.bookmark_name.is_some() {
+            return self.handle_bookmark_name_input(key_event);
+        }
+
+        if self.handle_history_keyboard_input(key_event) {
+            return true;
+        }
+
+        let previous_scene = self.scene();
+        let changed = self.handle_state_keyboard_input(key_event) || self.camera.handle_keyboard_input(key_event);
+        self.record_history(previous_scene);
+
+        changed
     }
 }
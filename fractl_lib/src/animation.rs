@@ -0,0 +1,528 @@
+use std::{fmt::Display, io::Write, num::NonZeroU32};
+
+#[cfg(feature = "image")]
+use std::path::Path;
+
+use cgmath::Vector2;
+
+use crate::{float, Color, Fill, Float, FrameBuffer, Scene};
+
+// Maps the time between two keyframes to the progress of the transition
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    const NUM_OF_VARIANTS: u8 = 4;
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Linear => 0,
+            Self::EaseIn => 1,
+            Self::EaseOut => 2,
+            Self::EaseInOut => 3,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Linear,
+            1 => Self::EaseIn,
+            2 => Self::EaseOut,
+            3 => Self::EaseInOut,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self::from_id(self.id() + 1)
+    }
+
+    #[must_use]
+    pub const fn prev(&self) -> Self {
+        Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
+    }
+
+    #[must_use]
+    pub fn ease(&self, t: Float) -> Float {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            // https://en.wikipedia.org/wiki/Smoothstep
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl Display for Easing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Easing::Linear => write!(f, "Linear"),
+            Easing::EaseIn => write!(f, "Ease in"),
+            Easing::EaseOut => write!(f, "Ease out"),
+            Easing::EaseInOut => write!(f, "Ease in and out"),
+        }?;
+
+        Ok(())
+    }
+}
+
+// The view at `time` seconds, `easing` is used for the transition from the previous keyframe
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time: Float,
+    pub center_pos: Vector2<Float>,
+    pub zoom: Vector2<Float>,
+    pub rotation: Float,
+    pub max_iterations: NonZeroU32,
+    pub palette_offset: Float,
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    #[must_use]
+    pub fn from_scene(time: Float, scene: &Scene, easing: Easing) -> Self {
+        Self {
+            time,
+            center_pos: scene.camera.center_pos,
+            zoom: scene.camera.zoom,
+            rotation: scene.camera.rotation,
+            max_iterations: scene.max_iterations,
+            palette_offset: scene.palette_offset,
+            easing,
+        }
+    }
+
+    fn validate(&self) -> Result<(), &'static str> {
+        if !(self.time.is_finite() && self.time >= 0.0) {
+            Err("keyframe time must be a finite number of seconds")
+        } else if !(self.center_pos.x.is_finite() && self.center_pos.y.is_finite()) {
+            Err("keyframe center must be finite")
+        } else if !(self.zoom.x.is_normal() && self.zoom.y.is_normal() && self.zoom.x > 0.0 && self.zoom.y > 0.0) {
+            Err("keyframe zoom must be larger than 0")
+        } else if !(self.rotation.is_finite() && self.palette_offset.is_finite()) {
+            Err("keyframe rotation and palette offset must be finite")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn interpolate(&self, next: &Self, progress: Float) -> Self {
+        let t = next.easing.ease(progress);
+        let lerp = |a: Float, b: Float| (b - a).mul_add(t, a);
+
+        // Interpolating the logarithm keeps the zoom speed constant
+        let zoom = self.zoom.zip(next.zoom, |a, b| lerp(a.ln(), b.ln()).exp());
+
+        // Zooms around a fixed point on screen like `Camera::zoom_at`, the center would otherwise race past the
+        // target while the view is still large
+        #[allow(clippy::float_cmp)]
+        let center_weight = if self.zoom.y == next.zoom.y {
+            t
+        } else {
+            (self.zoom.y.recip() - zoom.y.recip()) / (self.zoom.y.recip() - next.zoom.y.recip())
+        };
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let max_iterations =
+            NonZeroU32::new(lerp(float(self.max_iterations.get()), float(next.max_iterations.get())).round() as u32)
+                .unwrap_or(self.max_iterations);
+
+        Self {
+            time: (next.time - self.time).mul_add(progress, self.time),
+            center_pos: self.center_pos + (next.center_pos - self.center_pos) * center_weight,
+            zoom,
+            rotation: lerp(self.rotation, next.rotation),
+            max_iterations,
+            palette_offset: lerp(self.palette_offset, next.palette_offset),
+            easing: next.easing,
+        }
+    }
+}
+
+// Renders a sequence of frames by interpolating the view between keyframes
+#[derive(Clone, Debug)]
+pub struct Animation {
+    // Fractal, coloring and bailout shared by every frame
+    scene: Scene,
+    // Sorted by time
+    keyframes: Vec<Keyframe>,
+    frames_per_second: NonZeroU32,
+}
+
+impl Animation {
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(
+        scene: Scene,
+        mut keyframes: Vec<Keyframe>,
+        frames_per_second: NonZeroU32,
+    ) -> Result<Self, &'static str> {
+        if keyframes.is_empty() {
+            return Err("animation needs at least one keyframe");
+        }
+
+        for keyframe in &keyframes {
+            keyframe.validate()?;
+        }
+
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Ok(Self {
+            scene,
+            keyframes,
+            frames_per_second,
+        })
+    }
+
+    #[must_use]
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    #[must_use]
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    #[must_use]
+    pub fn frames_per_second(&self) -> NonZeroU32 {
+        self.frames_per_second
+    }
+
+    // In seconds, the time of the last keyframe
+    #[must_use]
+    pub fn duration(&self) -> Float {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn frame_count(&self) -> u32 {
+        (self.duration() * float(self.frames_per_second.get())).floor() as u32 + 1
+    }
+
+    #[must_use]
+    pub fn scene_at(&self, time: Float) -> Scene {
+        let keyframe = match self.keyframes.iter().position(|keyframe| keyframe.time > time) {
+            Some(0) => self.keyframes[0].clone(),
+            Some(next) => {
+                let (previous, next) = (&self.keyframes[next - 1], &self.keyframes[next]);
+                previous.interpolate(next, (time - previous.time) / (next.time - previous.time))
+            }
+            None => self.keyframes[self.keyframes.len() - 1].clone(),
+        };
+
+        let mut scene = self.scene.clone();
+        scene.camera.center_pos = keyframe.center_pos;
+        scene.camera.zoom = keyframe.zoom;
+        scene.camera.set_rotation(keyframe.rotation);
+        scene.max_iterations = keyframe.max_iterations;
+        scene.palette_offset = keyframe.palette_offset;
+
+        scene
+    }
+
+    #[must_use]
+    pub fn frame(&self, index: u32, screen_size: impl Into<Vector2<NonZeroU32>>) -> FrameBuffer {
+        let screen_size = screen_size.into();
+
+        let mut scene = self.scene_at(float(index) / float(self.frames_per_second.get()));
+        scene.camera.resize(screen_size);
+
+        let mut framebuffer = FrameBuffer::new(screen_size);
+        scene.fractal().fill(&mut framebuffer);
+
+        framebuffer
+    }
+
+    // Frames are rendered lazily, one per call to `next`
+    pub fn frames(&self, screen_size: impl Into<Vector2<NonZeroU32>>) -> impl Iterator<Item = FrameBuffer> + '_ {
+        let screen_size = screen_size.into();
+
+        (0..self.frame_count()).map(move |index| self.frame(index, screen_size))
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write_y4m(
+        &self,
+        writer: &mut impl Write,
+        screen_size: impl Into<Vector2<NonZeroU32>>,
+    ) -> Result<(), &'static str> {
+        write_y4m(writer, self.frames(screen_size), self.frames_per_second)
+    }
+
+    #[cfg(feature = "image")]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn save_png_sequence(
+        &self,
+        directory: &Path,
+        screen_size: impl Into<Vector2<NonZeroU32>>,
+    ) -> Result<(), &'static str> {
+        save_png_sequence(directory, self.frames(screen_size))
+    }
+}
+
+// Uncompressed 4:4:4 video, e.g. `ffmpeg -i zoom.y4m zoom.mp4`, every frame must have the same size
+#[allow(clippy::missing_errors_doc)]
+pub fn write_y4m(
+    writer: &mut impl Write,
+    frames: impl IntoIterator<Item = FrameBuffer>,
+    frames_per_second: NonZeroU32,
+) -> Result<(), &'static str> {
+    // https://wiki.multimedia.cx/index.php/YUV4MPEG2
+    const WRITE_ERROR: &str = "failed to write the video";
+
+    let mut frames = frames.into_iter().peekable();
+    let screen_size = *frames.peek().ok_or("video needs at least one frame")?.size();
+    let pixel_count = (screen_size.x.get() * screen_size.y.get()) as usize;
+
+    writeln!(
+        writer,
+        "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+        screen_size.x, screen_size.y, frames_per_second
+    )
+    .map_err(|_| WRITE_ERROR)?;
+
+    // Y, Cb and Cr planes one after another
+    let mut planes = vec![0; pixel_count * 3];
+
+    for framebuffer in frames {
+        if *framebuffer.size() != screen_size {
+            return Err("every frame of a video must have the same size");
+        }
+
+        for (index, color) in framebuffer.data.iter().enumerate() {
+            let [y, cb, cr] = ycbcr(*color);
+
+            planes[index] = y;
+            planes[pixel_count + index] = cb;
+            planes[pixel_count * 2 + index] = cr;
+        }
+
+        writer.write_all(b"FRAME\n").map_err(|_| WRITE_ERROR)?;
+        writer.write_all(&planes).map_err(|_| WRITE_ERROR)?;
+    }
+
+    writer.flush().map_err(|_| WRITE_ERROR)
+}
+
+// Saves frame_00000.png, frame_00001.png, ... into `directory`
+#[cfg(feature = "image")]
+#[allow(clippy::missing_errors_doc)]
+pub fn save_png_sequence(directory: &Path, frames: impl IntoIterator<Item = FrameBuffer>) -> Result<(), &'static str> {
+    for (index, framebuffer) in frames.into_iter().enumerate() {
+        framebuffer
+            .as_image()
+            .save(directory.join(format!("frame_{index:05}.png")))
+            .map_err(|_| "failed to save a frame")?;
+    }
+
+    Ok(())
+}
+
+// Limited range BT.601, which is what players assume for Y4M
+// https://en.wikipedia.org/wiki/YCbCr#ITU-R_BT.601_conversion
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn ycbcr(color: Color) -> [u8; 3] {
+    let (red, green, blue) = (
+        f32::from(color.red()) / 255.0,
+        f32::from(color.green()) / 255.0,
+        f32::from(color.blue()) / 255.0,
+    );
+
+    [
+        (16.0 + 65.481 * red + 128.553 * green + 24.966 * blue).round() as u8,
+        (128.0 - 37.797 * red - 74.203 * green + 112.0 * blue).round() as u8,
+        (128.0 + 112.0 * red - 93.786 * green - 18.214 * blue).round() as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{screen_size, Camera, ColorType, FractalType};
+
+    const TOLERANCE: Float = 1e-4;
+
+    fn keyframe(time: Float, center_pos: Vector2<Float>, zoom: Float, easing: Easing) -> Keyframe {
+        Keyframe {
+            time,
+            center_pos,
+            zoom: Vector2::new(zoom, zoom),
+            rotation: 0.0,
+            max_iterations: NonZeroU32::new(100).unwrap(),
+            palette_offset: 0.0,
+            easing,
+        }
+    }
+
+    // Zooms in 100 times in 2 seconds, then pans for a second without zooming
+    fn animation() -> Animation {
+        let scene = Scene {
+            camera: Camera::new(screen_size(16, 9)),
+            fractal_type: FractalType::Mandelbrot,
+            color_type: ColorType::default(),
+            max_iterations: NonZeroU32::new(100).unwrap(),
+            bailout: None,
+            palette_offset: 0.0,
+            relief: None,
+            orbit_trap: None,
+            interior_color_type: None,
+        };
+        let keyframes = vec![
+            keyframe(3.0, Vector2::new(1.0, 0.5), 100.0, Easing::EaseIn),
+            keyframe(0.0, Vector2::new(0.0, 0.0), 1.0, Easing::Linear),
+            Keyframe {
+                rotation: 1.0,
+                max_iterations: NonZeroU32::new(300).unwrap(),
+                palette_offset: 0.5,
+                ..keyframe(2.0, Vector2::new(-1.0, 0.5), 100.0, Easing::Linear)
+            },
+        ];
+
+        Animation::new(scene, keyframes, NonZeroU32::new(10).unwrap()).unwrap()
+    }
+
+    fn assert_close(a: Float, b: Float) {
+        assert!((a - b).abs() < TOLERANCE, "{a} != {b}");
+    }
+
+    fn assert_view(scene: &Scene, center_pos: Vector2<Float>, zoom: Float) {
+        assert_close(scene.camera.center_pos.x, center_pos.x);
+        assert_close(scene.camera.center_pos.y, center_pos.y);
+        assert_close(scene.camera.zoom.x, zoom);
+        assert_close(scene.camera.zoom.y, zoom);
+    }
+
+    fn frame(width: u32, height: u32, color: Color) -> FrameBuffer {
+        let mut framebuffer = FrameBuffer::new(screen_size(width, height));
+        framebuffer.map_pixels(|_| color);
+
+        framebuffer
+    }
+
+    #[test]
+    fn ease() {
+        for easing in (0..Easing::NUM_OF_VARIANTS).map(Easing::from_id) {
+            assert_close(easing.ease(0.0), 0.0);
+            assert_close(easing.ease(1.0), 1.0);
+            assert_close(easing.ease(-1.0), 0.0);
+            assert_close(easing.ease(2.0), 1.0);
+        }
+
+        assert_close(Easing::Linear.ease(0.5), 0.5);
+        assert_close(Easing::EaseIn.ease(0.5), 0.25);
+        assert_close(Easing::EaseOut.ease(0.5), 0.75);
+        assert_close(Easing::EaseInOut.ease(0.5), 0.5);
+    }
+
+    #[test]
+    fn new() {
+        let animation = animation();
+        let times = animation
+            .keyframes()
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .collect::<Vec<_>>();
+
+        assert_eq!(times, [0.0, 2.0, 3.0]);
+        assert_eq!(animation.frame_count(), 31);
+
+        let scene = animation.scene().clone();
+        let frames_per_second = animation.frames_per_second();
+        let zero_zoom = keyframe(0.0, Vector2::new(0.0, 0.0), 0.0, Easing::Linear);
+
+        assert!(Animation::new(scene.clone(), Vec::new(), frames_per_second).is_err());
+        assert!(Animation::new(scene, vec![zero_zoom], frames_per_second).is_err());
+    }
+
+    #[test]
+    fn scene_at_keyframes() {
+        let animation = animation();
+
+        assert_view(&animation.scene_at(0.0), Vector2::new(0.0, 0.0), 1.0);
+        assert_view(&animation.scene_at(3.0), Vector2::new(1.0, 0.5), 100.0);
+
+        let scene = animation.scene_at(2.0);
+        assert_view(&scene, Vector2::new(-1.0, 0.5), 100.0);
+        assert_close(scene.camera.rotation, 1.0);
+        assert_eq!(scene.max_iterations.get(), 300);
+        assert_close(scene.palette_offset, 0.5);
+
+        // Clamped to the first and the last keyframe
+        assert_eq!(animation.scene_at(-1.0), animation.scene_at(0.0));
+        assert_eq!(animation.scene_at(10.0), animation.scene_at(3.0));
+    }
+
+    #[test]
+    fn scene_at_midpoint() {
+        let animation = animation();
+
+        // The zoom is the geometric mean
+        let scene = animation.scene_at(1.0);
+        let center_weight = (1.0 - 1.0 / 10.0) / (1.0 - 1.0 / 100.0);
+        assert_view(&scene, Vector2::new(-center_weight, 0.5 * center_weight), 10.0);
+        assert_close(scene.camera.rotation, 0.5);
+        assert_eq!(scene.max_iterations.get(), 200);
+        assert_close(scene.palette_offset, 0.25);
+
+        // Like `Camera::zoom_at`, one point stays at the same place on screen during the whole transition
+        let fixed_point = Vector2::new(-1.0, 0.5) / (1.0 - 1.0 / 100.0);
+        let screen_offset = |time: Float| {
+            let scene = animation.scene_at(time);
+            (fixed_point - scene.camera.center_pos) * scene.camera.zoom.x
+        };
+        for time in [0.5, 1.0, 1.5, 2.0] {
+            assert_close(screen_offset(time).x, screen_offset(0.0).x);
+            assert_close(screen_offset(time).y, screen_offset(0.0).y);
+        }
+
+        // Without zooming the center moves with the eased progress
+        assert_view(&animation.scene_at(2.5), Vector2::new(-0.5, 0.5), 100.0);
+    }
+
+    #[test]
+    fn y4m() {
+        let mut video = Vec::new();
+        let frames = [frame(3, 2, Color::BLACK), frame(3, 2, Color::WHITE)];
+        write_y4m(&mut video, frames, NonZeroU32::new(30).unwrap()).unwrap();
+
+        let mut expected = b"YUV4MPEG2 W3 H2 F30:1 Ip A1:1 C444\n".to_vec();
+        for luma in [16, 235] {
+            expected.extend_from_slice(b"FRAME\n");
+            expected.extend_from_slice(&[luma; 6]);
+            expected.extend_from_slice(&[128; 12]);
+        }
+
+        assert_eq!(video, expected);
+    }
+
+    #[test]
+    fn y4m_errors() {
+        let frames_per_second = NonZeroU32::new(30).unwrap();
+
+        assert!(write_y4m(&mut Vec::new(), [], frames_per_second).is_err());
+        assert!(write_y4m(
+            &mut Vec::new(),
+            [frame(3, 2, Color::BLACK), frame(2, 3, Color::BLACK)],
+            frames_per_second
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn ycbcr_primaries() {
+        assert_eq!(ycbcr(Color::RED), [81, 90, 240]);
+    }
+}
@@ -0,0 +1,90 @@
+use cfg_if::cfg_if;
+use cgmath::Vector2;
+use num_complex::Complex;
+
+use crate::{float, Float};
+
+// https://jussiharkonen.github.io/files/on_fractal_coloring_techniques.pdf
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrbitAverage {
+    Stripe,
+    TriangleInequality,
+    Curvature,
+}
+
+impl OrbitAverage {
+    const STRIPE_DENSITY: Float = 5.0;
+}
+
+pub(crate) struct OrbitAverageTracker {
+    orbit_average: OrbitAverage,
+    c: Complex<Float>,
+    degree: Float,
+    previous_z: [Option<Complex<Float>>; 2],
+    sum: Float,
+    last: Float,
+    count: u32,
+}
+
+impl OrbitAverageTracker {
+    pub(crate) fn new(orbit_average: OrbitAverage, c: Vector2<Float>, degree: Float) -> Self {
+        Self {
+            orbit_average,
+            c: Complex::new(c.x, c.y),
+            degree,
+            previous_z: [None, None],
+            sum: 0.0,
+            last: 0.0,
+            count: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, z: Vector2<Float>) {
+        cfg_if! {
+            if #[cfg(feature = "f32")] {
+                const PI: f32 = std::f32::consts::PI;
+            } else if #[cfg(feature = "f64")] {
+                const PI: f64 = std::f64::consts::PI;
+            }
+        }
+
+        let z = Complex::new(z.x, z.y);
+
+        let value = match (self.orbit_average, self.previous_z) {
+            (OrbitAverage::Stripe, _) => Some(0.5 * (OrbitAverage::STRIPE_DENSITY * z.arg()).sin() + 0.5),
+            (OrbitAverage::TriangleInequality, [Some(previous_z), _]) => {
+                let previous_z_pow = previous_z.norm().powf(self.degree);
+                let (min, max) = ((previous_z_pow - self.c.norm()).abs(), previous_z_pow + self.c.norm());
+
+                Some((z.norm() - min) / (max - min)).filter(|value| value.is_finite())
+            }
+            (OrbitAverage::Curvature, [Some(previous_z), Some(previous_previous_z)]) => {
+                Some(((z - previous_z) / (previous_z - previous_previous_z)).arg().abs() / PI)
+                    .filter(|value| value.is_finite())
+            }
+            _ => None,
+        };
+
+        if let Some(value) = value {
+            self.sum += value;
+            self.last = value;
+            self.count += 1;
+        }
+
+        self.previous_z = [Some(z), self.previous_z[0]];
+    }
+
+    // Linear interpolation between the averages with and without the last orbit point
+    pub(crate) fn average(&self, smooth_fraction: Float) -> Option<Float> {
+        match self.count {
+            0 => None,
+            1 => Some(self.sum),
+            count => {
+                let average = self.sum / float(count);
+                let previous_average = (self.sum - self.last) / float(count - 1);
+
+                Some(smooth_fraction * average + (1.0 - smooth_fraction) * previous_average)
+            }
+        }
+    }
+}
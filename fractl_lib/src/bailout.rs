@@ -0,0 +1,199 @@
+use std::fmt::Display;
+
+use cgmath::Vector2;
+
+use crate::{Float, IterationFormula};
+
+// Norm the orbit is measured with when checking whether it escaped
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum BailoutNorm {
+    #[default]
+    Euclidean,
+    Manhattan,
+    Maximum,
+    Real,
+    Imaginary,
+}
+
+impl BailoutNorm {
+    const NUM_OF_VARIANTS: u8 = 5;
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Euclidean => 0,
+            Self::Manhattan => 1,
+            Self::Maximum => 2,
+            Self::Real => 3,
+            Self::Imaginary => 4,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Euclidean,
+            1 => Self::Manhattan,
+            2 => Self::Maximum,
+            3 => Self::Real,
+            4 => Self::Imaginary,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self::from_id(self.id() + 1)
+    }
+
+    #[must_use]
+    pub const fn prev(&self) -> Self {
+        Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
+    }
+
+    #[must_use]
+    pub fn norm(&self, z: Vector2<Float>) -> Float {
+        match self {
+            Self::Euclidean => (z.x.powi(2) + z.y.powi(2)).sqrt(),
+            Self::Manhattan => z.x.abs() + z.y.abs(),
+            Self::Maximum => z.x.abs().max(z.y.abs()),
+            Self::Real => z.x.abs(),
+            Self::Imaginary => z.y.abs(),
+        }
+    }
+}
+
+impl Display for BailoutNorm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BailoutNorm::Euclidean => write!(f, "Euclidean"),
+            BailoutNorm::Manhattan => write!(f, "Manhattan"),
+            BailoutNorm::Maximum => write!(f, "Maximum"),
+            BailoutNorm::Real => write!(f, "Real"),
+            BailoutNorm::Imaginary => write!(f, "Imaginary"),
+        }?;
+
+        Ok(())
+    }
+}
+
+// An orbit escapes once the norm of z gets larger than the radius
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bailout {
+    radius: Float,
+    norm: BailoutNorm,
+}
+
+impl Bailout {
+    // For formulas like Nova and Lyapunov whose orbits are not stopped by escaping
+    pub const NEVER: Self = Self {
+        radius: Float::INFINITY,
+        norm: BailoutNorm::Euclidean,
+    };
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(radius: Float, norm: BailoutNorm) -> Result<Self, &'static str> {
+        if radius.is_nan() || radius <= 0.0 {
+            Err("bailout radius must be larger than 0")
+        } else if radius.is_infinite() {
+            Err("bailout radius must be finite")
+        } else {
+            Ok(Self { radius, norm })
+        }
+    }
+
+    // Default bailout of a formula, falls back to the one of the Mandelbrot set if the formula gives an invalid radius
+    #[must_use]
+    pub fn of_formula<F: IterationFormula + ?Sized>(formula: &F) -> Self {
+        formula
+            .bailout()
+            .map_or(Ok(Self::NEVER), |radius| Self::new(radius, BailoutNorm::Euclidean))
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn radius(&self) -> Float {
+        self.radius
+    }
+
+    #[must_use]
+    pub fn norm(&self) -> BailoutNorm {
+        self.norm
+    }
+
+    #[must_use]
+    pub fn is_never(&self) -> bool {
+        self.radius == Float::INFINITY
+    }
+
+    #[must_use]
+    pub fn contains(&self, z: Vector2<Float>) -> bool {
+        match self.norm {
+            // Skips the square root of the common case
+            BailoutNorm::Euclidean => z.x.powi(2) + z.y.powi(2) <= self.radius.powi(2),
+            norm => norm.norm(z) <= self.radius,
+        }
+    }
+}
+
+impl Default for Bailout {
+    fn default() -> Self {
+        Self {
+            radius: 2.0,
+            norm: BailoutNorm::Euclidean,
+        }
+    }
+}
+
+impl Display for Bailout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_never() {
+            write!(f, "never")
+        } else {
+            write!(f, "{} norm, radius {}", self.norm, self.radius)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+
+    use super::*;
+    use crate::{FractalType, LyapunovSequence};
+
+    #[test]
+    fn new() {
+        assert!(Bailout::new(2.0, BailoutNorm::Manhattan).is_ok());
+        assert!(Bailout::new(0.0, BailoutNorm::Euclidean).is_err());
+        assert!(Bailout::new(-2.0, BailoutNorm::Euclidean).is_err());
+        assert!(Bailout::new(Float::NAN, BailoutNorm::Euclidean).is_err());
+        assert!(Bailout::new(Float::INFINITY, BailoutNorm::Euclidean).is_err());
+    }
+
+    #[test]
+    fn of_formula() {
+        assert_eq!(Bailout::of_formula(&FractalType::Mandelbrot), Bailout::default());
+        assert_eq!(
+            Bailout::of_formula(&FractalType::Multibrot(-3.0)),
+            Bailout::new(3.0, BailoutNorm::Euclidean).unwrap()
+        );
+        assert_eq!(Bailout::of_formula(&FractalType::Multibrot(0.0)), Bailout::default());
+        assert_eq!(
+            Bailout::of_formula(&FractalType::Nova(Complex::new(1.0, 0.0))),
+            Bailout::NEVER
+        );
+        assert!(Bailout::of_formula(&FractalType::Lyapunov(LyapunovSequence::AB)).is_never());
+    }
+
+    #[test]
+    fn contains() {
+        let z = Vector2::new(1.5, -1.5);
+
+        assert!(!Bailout::default().contains(z));
+        assert!(Bailout::new(2.0, BailoutNorm::Maximum).unwrap().contains(z));
+        assert!(!Bailout::new(2.0, BailoutNorm::Manhattan).unwrap().contains(z));
+        assert!(Bailout::new(1.5, BailoutNorm::Real).unwrap().contains(z));
+        assert!(Bailout::NEVER.contains(Vector2::new(1e30, 1e30)));
+    }
+}
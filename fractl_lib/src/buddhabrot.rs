@@ -0,0 +1,217 @@
+use std::{fmt::Display, num::NonZeroU32};
+
+use cfg_if::cfg_if;
+use cgmath::Vector2;
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+use crate::{
+    float, framebuffer::LinearColor, Camera, Fill, Float, FractalType, FrameBuffer, HdrFrameBuffer, IterationFormula,
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BuddhabrotType {
+    #[default]
+    Buddhabrot,
+    AntiBuddhabrot,
+}
+
+impl Display for BuddhabrotType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuddhabrotType::Buddhabrot => write!(f, "Buddhabrot"),
+            BuddhabrotType::AntiBuddhabrot => write!(f, "Anti-Buddhabrot"),
+        }?;
+
+        Ok(())
+    }
+}
+
+// https://en.wikipedia.org/wiki/Buddhabrot
+#[derive(Clone, Debug, PartialEq)]
+pub struct Buddhabrot {
+    kind: BuddhabrotType,
+    fractal_type: FractalType,
+    camera: Camera,
+    // Iteration limits of the red, green and blue channels, all equal unless rendering a Nebulabrot
+    max_iterations: [NonZeroU32; 3],
+    samples: u64,
+    seed: u64,
+}
+
+impl Buddhabrot {
+    const SAMPLES_PER_CHUNK: u64 = 1 << 14;
+    const SAMPLE_RADIUS: Float = 2.0;
+
+    #[must_use]
+    pub fn new(
+        kind: BuddhabrotType,
+        fractal_type: FractalType,
+        camera: Camera,
+        max_iterations: NonZeroU32,
+        samples: u64,
+        seed: u64,
+    ) -> Self {
+        Self::nebulabrot(kind, fractal_type, camera, [max_iterations; 3], samples, seed)
+    }
+
+    // https://en.wikipedia.org/wiki/Buddhabrot#Nuances
+    #[must_use]
+    pub fn nebulabrot(
+        kind: BuddhabrotType,
+        fractal_type: FractalType,
+        camera: Camera,
+        max_iterations: [NonZeroU32; 3],
+        samples: u64,
+        seed: u64,
+    ) -> Self {
+        Self {
+            kind,
+            fractal_type,
+            camera,
+            max_iterations,
+            samples,
+            seed,
+        }
+    }
+
+    #[must_use]
+    pub fn max_iterations(&self) -> [NonZeroU32; 3] {
+        self.max_iterations
+    }
+
+    #[must_use]
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Each chunk of samples gets its own rng seeded from `seed`, so the result does not depend on threading
+    fn add_chunk_to_histogram(&self, chunk: u64, screen_size: Vector2<NonZeroU32>, histogram: &mut [Vec<u32>; 3]) {
+        let mut rng = Pcg64Mcg::seed_from_u64(self.seed ^ chunk.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let max_iterations = self.max_iterations.iter().map(|x| x.get()).max().unwrap_or(1);
+        let bailout = self
+            .fractal_type
+            .bailout()
+            .map_or(Float::INFINITY, |radius| radius.powi(2));
+
+        let mut orbit = Vec::with_capacity(max_iterations as usize);
+
+        let chunk_samples = Self::SAMPLES_PER_CHUNK.min(self.samples - chunk * Self::SAMPLES_PER_CHUNK);
+        for _ in 0..chunk_samples {
+            let c = Complex::new(
+                rng.gen_range(-Self::SAMPLE_RADIUS..Self::SAMPLE_RADIUS),
+                rng.gen_range(-Self::SAMPLE_RADIUS..Self::SAMPLE_RADIUS),
+            );
+
+            // Points in the main bulb never escape
+            if self.kind == BuddhabrotType::Buddhabrot && self.fractal_type == FractalType::Mandelbrot {
+                let q = (c.re - 0.25).powi(2) + c.im.powi(2);
+                if q * (q + (c.re - 0.25)) <= 0.25 * c.im.powi(2) {
+                    continue;
+                }
+            }
+
+            orbit.clear();
+
+            let (mut z, mut previous_z) = (self.fractal_type.init(c), Complex::new(0.0, 0.0));
+            let mut escaped_at = None;
+            for n in 0..max_iterations {
+                (z, previous_z) = (self.fractal_type.step(z, previous_z, c), z);
+
+                if z.norm_sqr() > bailout {
+                    escaped_at = Some(n);
+                    break;
+                }
+
+                orbit.push(z);
+            }
+
+            for (channel, channel_max_iterations) in self.max_iterations.iter().enumerate() {
+                let orbit_len = match (self.kind, escaped_at) {
+                    (BuddhabrotType::Buddhabrot, Some(n)) if n < channel_max_iterations.get() => n as usize,
+                    (BuddhabrotType::AntiBuddhabrot, None) => orbit.len().min(channel_max_iterations.get() as usize),
+                    (BuddhabrotType::AntiBuddhabrot, Some(n)) if n >= channel_max_iterations.get() => {
+                        channel_max_iterations.get() as usize
+                    }
+                    _ => continue,
+                };
+
+                for z in &orbit[..orbit_len] {
+                    if let Some(screen_pos) = self.camera.world_to_screen_pos(&Vector2::new(z.re, z.im), &screen_size) {
+                        let index = (screen_pos.y * screen_size.x.get() + screen_pos.x) as usize;
+                        histogram[channel][index] = histogram[channel][index].saturating_add(1);
+                    }
+                }
+            }
+        }
+    }
+
+    // Full precision render, keeps the faint orbits of large sample counts that 8 bits round to black
+    pub fn fill_hdr(&self, buffer: &mut HdrFrameBuffer) {
+        let histogram = self.histogram(buffer.size());
+
+        let channel_max = [0, 1, 2].map(|channel| float(histogram[channel].iter().copied().max().unwrap_or(0).max(1)));
+
+        #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+        let brightness =
+            |channel: usize, index: usize| (float(histogram[channel][index]) / channel_max[channel]).sqrt() as f32;
+
+        buffer.data = (0..buffer.data.len())
+            .map(|index| LinearColor::from_srgb(brightness(0, index), brightness(1, index), brightness(2, index)))
+            .collect::<Vec<_>>();
+    }
+
+    #[must_use]
+    pub fn histogram(&self, screen_size: &Vector2<NonZeroU32>) -> [Vec<u32>; 3] {
+        #[cfg(feature = "multithread")]
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        let chunks = self.samples.div_ceil(Self::SAMPLES_PER_CHUNK);
+        let buffer_len = (screen_size.x.get() * screen_size.y.get()) as usize;
+        let empty = || [vec![0; buffer_len], vec![0; buffer_len], vec![0; buffer_len]];
+
+        cfg_if! {
+            if #[cfg(feature = "multithread")] {
+                // Every rayon job accumulates into one histogram, so memory grows with the thread count rather than
+                // the chunk count
+                (0..chunks)
+                    .into_par_iter()
+                    .fold(empty, |mut histogram, chunk| {
+                        self.add_chunk_to_histogram(chunk, *screen_size, &mut histogram);
+                        histogram
+                    })
+                    .reduce(empty, |mut a, b| {
+                        for (a, b) in a.iter_mut().zip(b.iter()) {
+                            for (a, b) in a.iter_mut().zip(b.iter()) {
+                                *a = a.saturating_add(*b);
+                            }
+                        }
+
+                        a
+                    })
+            } else {
+                let mut histogram = empty();
+                for chunk in 0..chunks {
+                    self.add_chunk_to_histogram(chunk, *screen_size, &mut histogram);
+                }
+
+                histogram
+            }
+        }
+    }
+}
+
+impl Fill for Buddhabrot {
+    fn fill(&self, buffer: &mut FrameBuffer) {
+        let mut hdr_buffer = HdrFrameBuffer::new(*buffer.size());
+        self.fill_hdr(&mut hdr_buffer);
+
+        *buffer = hdr_buffer.to_framebuffer(false);
+    }
+}
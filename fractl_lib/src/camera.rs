@@ -7,13 +7,26 @@ use winit::{
     keyboard::{KeyCode, PhysicalKey},
 };
 
-use crate::{float, Float};
+use cfg_if::cfg_if;
+
+use crate::{float, Float, Projection, ProjectionType};
+
+cfg_if! {
+    if #[cfg(feature = "f32")] {
+        use std::f32::consts::TAU;
+    } else if #[cfg(feature = "f64")] {
+        use std::f64::consts::TAU;
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Camera {
     pub(crate) center_pos: Vector2<Float>,
     pub(crate) view_size: Vector2<Float>,
     pub(crate) zoom: Vector2<Float>,
+    // Angle in radians, turns the image counterclockwise on screen
+    pub(crate) rotation: Float,
+    pub(crate) projection: ProjectionType,
 }
 
 impl Camera {
@@ -22,6 +35,8 @@ impl Camera {
     #[allow(dead_code)]
     const ZOOM_INCREMENT: Float = 0.02;
     #[allow(dead_code)]
+    const ROTATION_INCREMENT: Float = 0.05;
+    #[allow(dead_code)]
     const MIN_ZOOM: Float = 0.1;
     #[allow(dead_code)]
     const MAX_ZOOM: Float = Float::MAX;
@@ -32,6 +47,8 @@ impl Camera {
             center_pos: Vector2::new(0.0, 0.0),
             view_size: Vector2::new(Camera::calc_ratio(screen_size), 1.0),
             zoom: Vector2::new(1.0, 1.0),
+            rotation: 0.0,
+            projection: ProjectionType::default(),
         }
     }
 
@@ -51,6 +68,13 @@ impl Camera {
         self.view_size.zip(self.zoom, |x, y| x / y)
     }
 
+    #[must_use]
+    pub fn pixel_size(&self, screen_size: &Vector2<NonZeroU32>) -> Float {
+        let view_size = self.view_size();
+
+        (view_size.x / float(screen_size.x.get())).min(view_size.y / float(screen_size.y.get()))
+    }
+
     #[must_use]
     pub fn zoom(&self) -> Vector2<Float> {
         self.zoom
@@ -62,6 +86,42 @@ impl Camera {
         self.zoom = new_zoom;
     }
 
+    #[must_use]
+    pub fn rotation(&self) -> Float {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, new_rotation: Float) {
+        if new_rotation.is_finite() {
+            self.rotation = new_rotation.rem_euclid(TAU);
+        }
+    }
+
+    pub fn rotate(&mut self, by: Float) {
+        self.set_rotation(self.rotation + by);
+    }
+
+    #[must_use]
+    pub fn projection(&self) -> ProjectionType {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: ProjectionType) {
+        self.projection = projection;
+    }
+
+    // Pixels outside of the projection, e.g. around the Riemann sphere, show the background
+    #[must_use]
+    pub fn is_visible(&self, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> bool {
+        self.projection.is_visible(self, screen_pos, screen_size)
+    }
+
+    // Size of the pixel at `screen_pos`, only projections other than the plane make it depend on the position
+    #[must_use]
+    pub fn pixel_size_at(&self, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> Float {
+        self.projection.pixel_size(self, screen_pos, screen_size)
+    }
+
     pub fn resize(&mut self, new_screen_size: impl Into<Vector2<NonZeroU32>>) {
         self.view_size.x = Camera::calc_ratio(new_screen_size);
     }
@@ -77,22 +137,22 @@ impl Camera {
             if let PhysicalKey::Code(key_code) = key_event.physical_key {
                 match key_code {
                     KeyCode::KeyW => {
-                        self.center_pos.y -= (Camera::MOVE_INCREMENT) / self.zoom.y;
+                        self.center_pos += self.rotate_offset(Vector2::new(0.0, -Camera::MOVE_INCREMENT / self.zoom.y));
 
                         true
                     }
                     KeyCode::KeyS => {
-                        self.center_pos.y += (Camera::MOVE_INCREMENT) / self.zoom.y;
+                        self.center_pos += self.rotate_offset(Vector2::new(0.0, Camera::MOVE_INCREMENT / self.zoom.y));
 
                         true
                     }
                     KeyCode::KeyA => {
-                        self.center_pos.x -= (Camera::MOVE_INCREMENT) / self.zoom.x;
+                        self.center_pos += self.rotate_offset(Vector2::new(-Camera::MOVE_INCREMENT / self.zoom.x, 0.0));
 
                         true
                     }
                     KeyCode::KeyD => {
-                        self.center_pos.x += (Camera::MOVE_INCREMENT) / self.zoom.x;
+                        self.center_pos += self.rotate_offset(Vector2::new(Camera::MOVE_INCREMENT / self.zoom.x, 0.0));
 
                         true
                     }
@@ -135,6 +195,26 @@ impl Camera {
 
                         true
                     }
+                    KeyCode::Comma => {
+                        self.rotate(Camera::ROTATION_INCREMENT);
+
+                        true
+                    }
+                    KeyCode::Period => {
+                        self.rotate(-Camera::ROTATION_INCREMENT);
+
+                        true
+                    }
+                    KeyCode::Slash => {
+                        self.rotation = 0.0;
+
+                        true
+                    }
+                    KeyCode::KeyF => {
+                        self.projection = self.projection.next();
+
+                        true
+                    }
                     KeyCode::KeyR => {
                         self.center_pos = Vector2::new(0.0, 0.0);
 
@@ -161,13 +241,200 @@ impl Camera {
         }
     }
 
+    // Zooms by `factor` while the world position under `screen_pos` stays in place
+    pub fn zoom_at(&mut self, factor: Float, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) {
+        let new_zoom = self
+            .zoom
+            .map(|x| (x * factor).clamp(Camera::MIN_ZOOM, Camera::MAX_ZOOM));
+
+        if new_zoom.x.is_normal() && new_zoom.y.is_normal() {
+            let plane_pos = self.screen_to_plane_pos(screen_pos, screen_size);
+            self.zoom = new_zoom;
+            self.center_pos += plane_pos - self.screen_to_plane_pos(screen_pos, screen_size);
+        }
+    }
+
+    // Moves the view so the world position under `from` ends up under `to`
+    pub fn pan(&mut self, from: &Vector2<u32>, to: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) {
+        self.center_pos += self.screen_to_plane_pos(from, screen_size) - self.screen_to_plane_pos(to, screen_size);
+    }
+
+    // Centers the view on the rectangle between two screen corners and zooms in until it fills the screen
+    pub fn zoom_to_rect(
+        &mut self,
+        corner: &Vector2<u32>,
+        opposite_corner: &Vector2<u32>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) {
+        let (min, max) = (
+            corner.zip(*opposite_corner, u32::min),
+            corner.zip(*opposite_corner, u32::max),
+        );
+        let rect_size = (max - min).map(|x| float(x.max(1)));
+        let factor = (float(screen_size.x.get()) / rect_size.x).min(float(screen_size.y.get()) / rect_size.y);
+
+        let new_center_pos = self.screen_to_plane_pos(&((min + max) / 2), screen_size);
+        let new_zoom = self
+            .zoom
+            .map(|x| (x * factor).clamp(Camera::MIN_ZOOM, Camera::MAX_ZOOM));
+
+        if new_zoom.x.is_normal() && new_zoom.y.is_normal() {
+            self.center_pos = new_center_pos;
+            self.zoom = new_zoom;
+        }
+    }
+
     #[must_use]
     pub fn screen_to_world_pos(&self, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> Vector2<Float> {
+        self.projection.screen_to_world_pos(self, screen_pos, screen_size)
+    }
+
+    // Screen position of the pixel containing `world_pos`, `None` if it lies outside of the screen
+    #[must_use]
+    pub fn world_to_screen_pos(
+        &self,
+        world_pos: &Vector2<Float>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) -> Option<Vector2<u32>> {
+        self.projection.world_to_screen_pos(self, world_pos, screen_size)
+    }
+
+    // Position in the plane the camera moves in, before the projection is applied
+    // Panning and zooming work in this plane, so they behave the same for every projection
+    #[must_use]
+    pub fn screen_to_plane_pos(&self, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> Vector2<Float> {
         let screen_pos_normalized = screen_pos.zip(*screen_size, |pos, size| (float(pos) / float(size.get())) - 0.5);
 
-        Vector2::new(
-            ((screen_pos_normalized.x * self.view_size.x) / self.zoom.x) + self.center_pos.x,
-            ((screen_pos_normalized.y * self.view_size.y) / self.zoom.y) + self.center_pos.y,
-        )
+        self.rotate_offset(Vector2::new(
+            (screen_pos_normalized.x * self.view_size.x) / self.zoom.x,
+            (screen_pos_normalized.y * self.view_size.y) / self.zoom.y,
+        )) + self.center_pos
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn plane_to_screen_pos(
+        &self,
+        plane_pos: &Vector2<Float>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) -> Option<Vector2<u32>> {
+        let view_size = self.view_size();
+        let offset = rotate(plane_pos - self.center_pos, -self.rotation);
+
+        let screen_pos = Vector2::new(
+            (offset.x / view_size.x + 0.5) * float(screen_size.x.get()),
+            (offset.y / view_size.y + 0.5) * float(screen_size.y.get()),
+        );
+
+        if (0.0..float(screen_size.x.get())).contains(&screen_pos.x)
+            && (0.0..float(screen_size.y.get())).contains(&screen_pos.y)
+        {
+            Some(screen_pos.map(|x| x as u32))
+        } else {
+            None
+        }
+    }
+
+    // Turns an offset from the center of the screen into world space
+    pub(crate) fn rotate_offset(&self, offset: Vector2<Float>) -> Vector2<Float> {
+        rotate(offset, self.rotation)
+    }
+}
+
+pub(crate) fn rotate(offset: Vector2<Float>, angle: Float) -> Vector2<Float> {
+    let (sin, cos) = angle.sin_cos();
+
+    Vector2::new(offset.x * cos - offset.y * sin, offset.x * sin + offset.y * cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen_size;
+
+    const TOLERANCE: Float = 1e-4;
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new(screen_size(160, 90));
+        camera.set_center_pos(Vector2::new(-0.5, 0.25));
+        camera.set_zoom(Vector2::new(3.0, 3.0));
+        camera.set_rotation(0.3);
+
+        camera
+    }
+
+    fn assert_close(a: Vector2<Float>, b: Vector2<Float>) {
+        assert!(
+            (a.x - b.x).abs() < TOLERANCE && (a.y - b.y).abs() < TOLERANCE,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn world_to_screen_pos() {
+        let (camera, screen_size) = (camera(), screen_size(160, 90));
+        // A quarter of a pixel into the pixel, so rounding cannot land on a neighbour
+        let inside_pixel = camera.rotate_offset(Vector2::new(1.0, 1.0) * camera.pixel_size(&screen_size) / 4.0);
+
+        for screen_pos in [
+            Vector2::new(0, 0),
+            Vector2::new(80, 45),
+            Vector2::new(159, 89),
+            Vector2::new(17, 63),
+        ] {
+            let world_pos = camera.screen_to_world_pos(&screen_pos, &screen_size) + inside_pixel;
+
+            assert_eq!(camera.world_to_screen_pos(&world_pos, &screen_size), Some(screen_pos));
+        }
+
+        assert_eq!(
+            camera.world_to_screen_pos(&Vector2::new(100.0, 100.0), &screen_size),
+            None
+        );
+    }
+
+    #[test]
+    fn zoom_at() {
+        let screen_size = screen_size(160, 90);
+        let screen_pos = Vector2::new(20, 70);
+
+        let mut camera = camera();
+        let world_pos = camera.screen_to_world_pos(&screen_pos, &screen_size);
+
+        camera.zoom_at(2.5, &screen_pos, &screen_size);
+        assert_close(camera.screen_to_world_pos(&screen_pos, &screen_size), world_pos);
+        assert_close(camera.zoom(), Vector2::new(7.5, 7.5));
+
+        camera.zoom_at(1.0 / 2.5, &screen_pos, &screen_size);
+        assert_close(camera.center_pos(), self::camera().center_pos());
+    }
+
+    #[test]
+    fn pan() {
+        let screen_size = screen_size(160, 90);
+        let (from, to) = (Vector2::new(10, 80), Vector2::new(120, 5));
+
+        let mut camera = camera();
+        let world_pos = camera.screen_to_world_pos(&from, &screen_size);
+
+        camera.pan(&from, &to, &screen_size);
+        assert_close(camera.screen_to_world_pos(&to, &screen_size), world_pos);
+
+        camera.pan(&to, &from, &screen_size);
+        assert_close(camera.center_pos(), self::camera().center_pos());
+    }
+
+    #[test]
+    fn zoom_to_rect() {
+        let screen_size = screen_size(160, 90);
+        let (corner, opposite_corner) = (Vector2::new(100, 60), Vector2::new(60, 40));
+
+        let mut camera = camera();
+        let rect_center = camera.screen_to_plane_pos(&Vector2::new(80, 50), &screen_size);
+
+        camera.zoom_to_rect(&corner, &opposite_corner, &screen_size);
+        assert_close(camera.center_pos(), rect_center);
+        // The 40 x 20 rectangle is limited by its width on the 160 x 90 screen
+        assert_close(camera.zoom(), Vector2::new(12.0, 12.0));
     }
 }
@@ -0,0 +1,226 @@
+use std::num::NonZeroU32;
+
+use cfg_if::cfg_if;
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::{float, Float};
+
+cfg_if! {
+    if #[cfg(feature = "f32")] {
+        use std::f32::consts::{FRAC_PI_2, PI};
+    } else if #[cfg(feature = "f64")] {
+        use std::f64::consts::{FRAC_PI_2, PI};
+    }
+}
+
+// Pinhole camera looking along `direction`, the z axis points up
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera3D {
+    pub(crate) position: Vector3<Float>,
+    pub(crate) direction: Vector3<Float>,
+    // Vertical field of view in radians
+    pub(crate) field_of_view: Float,
+}
+
+impl Camera3D {
+    const UP: Vector3<Float> = Vector3::new(0.0, 0.0, 1.0);
+    // Keeps the direction from becoming parallel to `UP`
+    const MAX_PITCH: Float = FRAC_PI_2 - 0.01;
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(position: Vector3<Float>, target: Vector3<Float>, field_of_view: Float) -> Result<Self, &'static str> {
+        let mut camera = Self {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            direction: Vector3::new(1.0, 0.0, 0.0),
+            field_of_view: 1.0,
+        };
+
+        camera.set_position(position)?;
+        camera.look_at(target)?;
+        camera.set_field_of_view(field_of_view)?;
+
+        Ok(camera)
+    }
+
+    #[must_use]
+    pub fn position(&self) -> Vector3<Float> {
+        self.position
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_position(&mut self, position: Vector3<Float>) -> Result<(), &'static str> {
+        if position.x.is_finite() && position.y.is_finite() && position.z.is_finite() {
+            self.position = position;
+            Ok(())
+        } else {
+            Err("position must be finite")
+        }
+    }
+
+    #[must_use]
+    pub fn direction(&self) -> Vector3<Float> {
+        self.direction
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn look_at(&mut self, target: Vector3<Float>) -> Result<(), &'static str> {
+        let direction = target - self.position;
+
+        if !(direction.x.is_finite() && direction.y.is_finite() && direction.z.is_finite()) {
+            Err("target must be finite")
+        } else if direction.magnitude2() == 0.0 {
+            Err("target cannot be at the position of the camera")
+        } else if direction.normalize().dot(Self::UP).abs().asin() > Self::MAX_PITCH {
+            Err("camera cannot look straight up or down")
+        } else {
+            self.direction = direction.normalize();
+            Ok(())
+        }
+    }
+
+    #[must_use]
+    pub fn field_of_view(&self) -> Float {
+        self.field_of_view
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_field_of_view(&mut self, field_of_view: Float) -> Result<(), &'static str> {
+        if field_of_view.is_finite() && field_of_view > 0.0 && field_of_view < PI {
+            self.field_of_view = field_of_view;
+            Ok(())
+        } else {
+            Err("field of view must be an angle between 0 and pi (exclusive)")
+        }
+    }
+
+    // Turns the camera in place, yaw around the z axis and pitch towards it
+    pub fn rotate(&mut self, yaw: Float, pitch: Float) {
+        let new_yaw = self.direction.y.atan2(self.direction.x) + yaw;
+        let new_pitch = (self.direction.z.asin() + pitch).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+
+        if new_yaw.is_finite() && new_pitch.is_finite() {
+            self.direction = Vector3::new(
+                new_pitch.cos() * new_yaw.cos(),
+                new_pitch.cos() * new_yaw.sin(),
+                new_pitch.sin(),
+            );
+        }
+    }
+
+    // Moves the camera relative to where it is looking, x to the right, y forward and z up
+    pub fn translate(&mut self, by: Vector3<Float>) {
+        let (right, up) = self.basis();
+        let new_position = self.position + right * by.x + self.direction * by.y + up * by.z;
+
+        if new_position.x.is_finite() && new_position.y.is_finite() && new_position.z.is_finite() {
+            self.position = new_position;
+        }
+    }
+
+    // Right and up vectors of the image plane
+    fn basis(&self) -> (Vector3<Float>, Vector3<Float>) {
+        let right = self.direction.cross(Self::UP).normalize();
+
+        (right, right.cross(self.direction))
+    }
+
+    #[must_use]
+    pub fn ray_direction(&self, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> Vector3<Float> {
+        let (right, up) = self.basis();
+        let half_height = (self.field_of_view / 2.0).tan();
+        let aspect_ratio = float(screen_size.x.get()) / float(screen_size.y.get());
+
+        let screen_pos_normalized = screen_pos.zip(*screen_size, |pos, size| {
+            ((float(pos) + 0.5) / float(size.get())) * 2.0 - 1.0
+        });
+
+        (self.direction + right * (screen_pos_normalized.x * half_height * aspect_ratio)
+            - up * (screen_pos_normalized.y * half_height))
+            .normalize()
+    }
+}
+
+impl Default for Camera3D {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0.0, -3.2, 1.0),
+            direction: Vector3::new(0.0, 3.2, -1.0).normalize(),
+            field_of_view: 0.8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen_size;
+
+    const TOLERANCE: Float = 1e-4;
+
+    fn assert_close(a: Vector3<Float>, b: Vector3<Float>) {
+        assert!((a - b).magnitude() < TOLERANCE, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn look_at() {
+        let mut camera = Camera3D::default();
+
+        camera.look_at(Vector3::new(1.0, -3.2, 1.0)).unwrap();
+        assert_close(camera.direction(), Vector3::new(1.0, 0.0, 0.0));
+
+        assert!(camera.look_at(Vector3::new(0.0, -3.2, 5.0)).is_err());
+        assert!(camera.look_at(Vector3::new(0.0, -3.2, -5.0)).is_err());
+        assert!(camera.look_at(camera.position()).is_err());
+        assert!(camera.look_at(Vector3::new(Float::NAN, 0.0, 0.0)).is_err());
+        // A rejected target keeps the old direction
+        assert_close(camera.direction(), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn new() {
+        let (position, target) = (Vector3::new(0.0, -3.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+
+        assert!(Camera3D::new(position, target, 1.0).is_ok());
+        assert!(Camera3D::new(position, target, 0.0).is_err());
+        assert!(Camera3D::new(position, target, PI).is_err());
+        assert!(Camera3D::new(position, position, 1.0).is_err());
+    }
+
+    #[test]
+    fn ray_direction() {
+        let camera = Camera3D::default();
+        let screen_size = screen_size(9, 5);
+
+        assert_close(
+            camera.ray_direction(&Vector2::new(4, 2), &screen_size),
+            camera.direction(),
+        );
+
+        // The centers of the top and bottom pixels are 0.8 of the way to the edges of the field of view
+        let (top, bottom) = (
+            camera.ray_direction(&Vector2::new(4, 0), &screen_size),
+            camera.ray_direction(&Vector2::new(4, 4), &screen_size),
+        );
+        let angle = (0.8 * (camera.field_of_view() / 2.0).tan()).atan();
+
+        assert!(top.z > camera.direction().z && bottom.z < camera.direction().z);
+        assert!((top.angle(camera.direction()).0 - angle).abs() < TOLERANCE);
+        assert!((bottom.angle(camera.direction()).0 - angle).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn rotate_and_translate() {
+        let mut camera = Camera3D::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), 1.0).unwrap();
+
+        camera.rotate(FRAC_PI_2, 0.0);
+        assert_close(camera.direction(), Vector3::new(0.0, 1.0, 0.0));
+
+        // The pitch stops short of looking straight up
+        camera.rotate(0.0, PI);
+        assert!(camera.direction().z < 1.0 && camera.direction().z > 0.99);
+
+        camera.rotate(0.0, -camera.direction().z.asin());
+        camera.translate(Vector3::new(1.0, 2.0, 3.0));
+        assert_close(camera.position(), Vector3::new(1.0, 2.0, 3.0));
+    }
+}
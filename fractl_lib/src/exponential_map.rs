@@ -0,0 +1,175 @@
+use std::num::NonZeroU32;
+
+use cfg_if::cfg_if;
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{float, Color, Float, FrameBuffer};
+
+cfg_if! {
+    if #[cfg(feature = "f32")] {
+        use std::f32::consts::TAU;
+    } else if #[cfg(feature = "f64")] {
+        use std::f64::consts::TAU;
+    }
+}
+
+// Rebuilds ordinary frames of a zoom from one strip rendered with `ProjectionType::ExponentialMap`
+// Every frame samples the same strip, so a whole zoom video costs about as much as rendering the strip once
+#[derive(Clone, Debug)]
+pub struct ExponentialMap {
+    strip: FrameBuffer,
+}
+
+impl ExponentialMap {
+    #[must_use]
+    pub fn new(strip: FrameBuffer) -> Self {
+        Self { strip }
+    }
+
+    #[must_use]
+    pub fn strip(&self) -> &FrameBuffer {
+        &self.strip
+    }
+
+    // Deepest zoom, relative to the camera of the strip, at which the strip still reaches within one pixel of the
+    // center of a frame, anything closer repeats the bottom row
+    #[must_use]
+    pub fn max_zoom(&self, screen_size: &Vector2<NonZeroU32>) -> Float {
+        let strip_size = self.strip.size().map(|x| float(x.get()));
+
+        ((TAU * strip_size.y / strip_size.x).exp() / float(screen_size.y.get())).max(1.0)
+    }
+
+    // Frame with the center and rotation of the camera of the strip, `zoom` is relative to its zoom
+    #[must_use]
+    pub fn frame(&self, zoom: Float, screen_size: impl Into<Vector2<NonZeroU32>>) -> FrameBuffer {
+        let screen_size = screen_size.into();
+        let strip_width = float(self.strip.size().x.get());
+        let aspect_ratio = float(screen_size.x.get()) / float(screen_size.y.get());
+
+        let mut framebuffer = FrameBuffer::new(screen_size);
+        framebuffer.map_pixels(|screen_pos| {
+            // Offset from the center in units of the radius of the top row of the strip
+            let offset = Vector2::new(
+                (float(screen_pos.x) / float(screen_size.x.get()) - 0.5) * aspect_ratio,
+                float(screen_pos.y) / float(screen_size.y.get()) - 0.5,
+            ) / zoom;
+
+            self.sample(Vector2::new(
+                offset.y.atan2(offset.x).rem_euclid(TAU) * strip_width / TAU,
+                -offset.magnitude().ln() * strip_width / TAU,
+            ))
+        });
+
+        framebuffer
+    }
+
+    // `count` frames zooming in from the camera of the strip to `max_zoom` at a constant speed
+    pub fn frames(
+        &self,
+        count: NonZeroU32,
+        screen_size: impl Into<Vector2<NonZeroU32>>,
+    ) -> impl Iterator<Item = FrameBuffer> + '_ {
+        let screen_size = screen_size.into();
+        let log_max_zoom = self.max_zoom(&screen_size).ln();
+        let last_index = float(count.get() - 1).max(1.0);
+
+        (0..count.get()).map(move |index| self.frame((log_max_zoom * float(index) / last_index).exp(), screen_size))
+    }
+
+    // Bilinear sample, the angle wraps around and the distance is clamped to the strip
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn sample(&self, strip_pos: Vector2<Float>) -> Color {
+        let size = *self.strip.size();
+        let strip_pos = Vector2::new(strip_pos.x, strip_pos.y.clamp(0.0, float(size.y.get() - 1)));
+
+        let (x, y) = (strip_pos.x.floor(), strip_pos.y.floor());
+        let (x_fract, y_fract) = ((strip_pos.x - x) as f32, (strip_pos.y - y) as f32);
+
+        let x = (x as i64).rem_euclid(i64::from(size.x.get())) as u32;
+        let y = y as u32;
+        let (next_x, next_y) = ((x + 1) % size.x.get(), (y + 1).min(size.y.get() - 1));
+
+        mix(
+            mix(
+                self.strip[Vector2::new(x, y)],
+                self.strip[Vector2::new(next_x, y)],
+                x_fract,
+            ),
+            mix(
+                self.strip[Vector2::new(x, next_y)],
+                self.strip[Vector2::new(next_x, next_y)],
+                x_fract,
+            ),
+            y_fract,
+        )
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let mix_channel = |a: u8, b: u8| (f32::from(b) - f32::from(a)).mul_add(t, f32::from(a)).round() as u8;
+
+    Color::new_rgba(
+        mix_channel(a.red(), b.red()),
+        mix_channel(a.green(), b.green()),
+        mix_channel(a.blue(), b.blue()),
+        mix_channel(a.alpha(), b.alpha()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen_size;
+
+    // 64 x 64 strip with the column in the red channel and the row in the green channel
+    #[allow(clippy::cast_possible_truncation)]
+    fn exponential_map() -> ExponentialMap {
+        let mut strip = FrameBuffer::new(screen_size(64, 64));
+        strip.map_pixels(|strip_pos| Color::new((strip_pos.x * 4) as u8, (strip_pos.y * 4) as u8, 0));
+
+        ExponentialMap::new(strip)
+    }
+
+    fn assert_pixel(frame: &FrameBuffer, screen_pos: (u32, u32), column: f32, row: f32) {
+        let color = frame[Vector2::new(screen_pos.0, screen_pos.1)];
+
+        assert!(
+            (f32::from(color.red()) - column * 4.0).abs() <= 1.0 && (f32::from(color.green()) - row * 4.0).abs() <= 1.0,
+            "{screen_pos:?}: {color:?}"
+        );
+    }
+
+    #[test]
+    fn frame() {
+        let exponential_map = exponential_map();
+
+        // A third of the radius of the top row away from the center, in the directions of the angles 0, pi / 2 and pi
+        let frame = exponential_map.frame(1.0, screen_size(40, 30));
+        assert_pixel(&frame, (30, 15), 0.0, 11.19);
+        assert_pixel(&frame, (20, 25), 16.0, 11.19);
+        assert_pixel(&frame, (10, 15), 32.0, 11.19);
+        // The center repeats the bottom row
+        assert_pixel(&frame, (20, 15), 0.0, 63.0);
+
+        let frame = exponential_map.frame(2.0, screen_size(40, 30));
+        assert_pixel(&frame, (30, 15), 0.0, 18.25);
+    }
+
+    #[test]
+    fn frames() {
+        let exponential_map = exponential_map();
+        let screen_size = screen_size(40, 30);
+        let max_zoom = exponential_map.max_zoom(&screen_size);
+
+        assert!((max_zoom - TAU.exp() / 30.0).abs() < 1e-3);
+
+        let frames = exponential_map
+            .frames(NonZeroU32::new(5).unwrap(), screen_size)
+            .collect::<Vec<_>>();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0], exponential_map.frame(1.0, screen_size));
+        assert_eq!(frames[4], exponential_map.frame(max_zoom, screen_size));
+    }
+}
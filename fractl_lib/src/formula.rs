@@ -0,0 +1,626 @@
+use std::{fmt::Display, iter::Peekable, str::Chars};
+
+use cfg_if::cfg_if;
+use num_complex::Complex;
+
+use crate::Float;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Z,
+    C,
+    I,
+    Constant(Float),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Abs,
+    Conj,
+    Sin,
+    Cos,
+    Exp,
+    Log,
+}
+
+impl Op {
+    const fn function(name: &str) -> Option<Self> {
+        match name.as_bytes() {
+            b"abs" => Some(Self::Abs),
+            b"conj" => Some(Self::Conj),
+            b"sin" => Some(Self::Sin),
+            b"cos" => Some(Self::Cos),
+            b"exp" => Some(Self::Exp),
+            b"log" => Some(Self::Log),
+            _ => None,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Z => "z",
+            Self::C => "c",
+            Self::I => "i",
+            Self::Constant(_) => "constant",
+            Self::Neg => "neg",
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Mul => "mul",
+            Self::Div => "div",
+            Self::Pow => "pow",
+            Self::Abs => "abs",
+            Self::Conj => "conj",
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Exp => "exp",
+            Self::Log => "log",
+        }
+    }
+}
+
+// Value of an expression together with its derivative, used for distance estimation
+#[derive(Clone, Copy, Debug, Default)]
+struct Dual {
+    value: Complex<Float>,
+    derivative: Complex<Float>,
+}
+
+impl Dual {
+    const MAX_INTEGER_EXPONENT: Float = 64.0;
+
+    fn constant(value: Complex<Float>) -> Self {
+        Self {
+            value,
+            derivative: Complex::new(0.0, 0.0),
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            value: self.value * other.value,
+            derivative: self.derivative * other.value + self.value * other.derivative,
+        }
+    }
+
+    fn div(self, other: Self) -> Self {
+        Self {
+            value: self.value / other.value,
+            derivative: (self.derivative * other.value - self.value * other.derivative) / (other.value * other.value),
+        }
+    }
+
+    #[allow(clippy::float_cmp, clippy::cast_possible_truncation)]
+    fn complex_pow(base: Complex<Float>, exponent: Complex<Float>) -> Complex<Float> {
+        if exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= Self::MAX_INTEGER_EXPONENT {
+            base.powi(exponent.re as i32)
+        } else if base == Complex::new(0.0, 0.0) {
+            base
+        } else {
+            (exponent * base.ln()).exp()
+        }
+    }
+
+    fn pow(self, exponent: Self) -> Self {
+        let value = Self::complex_pow(self.value, exponent.value);
+
+        Self {
+            value,
+            derivative: if exponent.derivative == Complex::new(0.0, 0.0) {
+                exponent.value * Self::complex_pow(self.value, exponent.value - 1.0) * self.derivative
+            } else {
+                value * (exponent.derivative * self.value.ln() + exponent.value * self.derivative / self.value)
+            },
+        }
+    }
+
+    // Component-wise, as in the Burning Ship fractal
+    fn abs(self) -> Self {
+        let sign = |x: Float| if x < 0.0 { -1.0 } else { 1.0 };
+
+        Self {
+            value: Complex::new(self.value.re.abs(), self.value.im.abs()),
+            derivative: Complex::new(
+                sign(self.value.re) * self.derivative.re,
+                sign(self.value.im) * self.derivative.im,
+            ),
+        }
+    }
+}
+
+// Complex-valued iteration formula in z and c, stored in reverse polish notation
+// so that it stays `Copy` like the rest of `FractalType`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Formula {
+    ops: [Op; Formula::MAX_LEN],
+    len: u8,
+}
+
+impl Formula {
+    pub const MAX_LEN: usize = 32;
+    // abs(z)^2 + c
+    pub const BURNING_SHIP: Self = Self::from_ops(&[Op::Z, Op::Abs, Op::Constant(2.0), Op::Pow, Op::C, Op::Add]);
+
+    pub(crate) const BAILOUT: Float = 4.0;
+
+    const fn from_ops(ops: &[Op]) -> Self {
+        let mut formula = Self {
+            ops: [Op::Z; Self::MAX_LEN],
+            len: 0,
+        };
+
+        while (formula.len as usize) < ops.len() {
+            formula.ops[formula.len as usize] = ops[formula.len as usize];
+            formula.len += 1;
+        }
+
+        formula
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(formula: &str) -> Result<Self, &'static str> {
+        let mut parser = Parser {
+            chars: formula.chars().peekable(),
+            formula: Self::from_ops(&[]),
+            depth: 0,
+        };
+
+        if parser.peek().is_none() {
+            return Err("formula cannot be empty string");
+        }
+
+        parser.expression()?;
+
+        if parser.peek().is_some() {
+            Err("unexpected character in formula")
+        } else {
+            Ok(parser.formula)
+        }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> u8 {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn ops(&self) -> &[Op] {
+        &self.ops[..self.len as usize]
+    }
+
+    // Returns the next z and its derivative, given the derivatives of z and c with respect to the same variable
+    pub(crate) fn evaluate(
+        &self,
+        z: Complex<Float>,
+        dz: Complex<Float>,
+        c: Complex<Float>,
+        dc: Complex<Float>,
+    ) -> (Complex<Float>, Complex<Float>) {
+        let mut stack = [Dual::default(); Self::MAX_LEN];
+        let mut len = 0;
+
+        for op in self.ops() {
+            let result = match *op {
+                Op::Z => Dual {
+                    value: z,
+                    derivative: dz,
+                },
+                Op::C => Dual {
+                    value: c,
+                    derivative: dc,
+                },
+                Op::I => Dual::constant(Complex::new(0.0, 1.0)),
+                Op::Constant(constant) => Dual::constant(Complex::new(constant, 0.0)),
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => {
+                    let (a, b) = (stack[len - 2], stack[len - 1]);
+                    len -= 2;
+
+                    match *op {
+                        Op::Add => Dual {
+                            value: a.value + b.value,
+                            derivative: a.derivative + b.derivative,
+                        },
+                        Op::Sub => Dual {
+                            value: a.value - b.value,
+                            derivative: a.derivative - b.derivative,
+                        },
+                        Op::Mul => a.mul(b),
+                        Op::Div => a.div(b),
+                        _ => a.pow(b),
+                    }
+                }
+                Op::Neg | Op::Abs | Op::Conj | Op::Sin | Op::Cos | Op::Exp | Op::Log => {
+                    let a = stack[len - 1];
+                    len -= 1;
+
+                    match *op {
+                        Op::Neg => Dual {
+                            value: -a.value,
+                            derivative: -a.derivative,
+                        },
+                        Op::Abs => a.abs(),
+                        Op::Conj => Dual {
+                            value: a.value.conj(),
+                            derivative: a.derivative.conj(),
+                        },
+                        Op::Sin => Dual {
+                            value: a.value.sin(),
+                            derivative: a.value.cos() * a.derivative,
+                        },
+                        Op::Cos => Dual {
+                            value: a.value.cos(),
+                            derivative: -a.value.sin() * a.derivative,
+                        },
+                        Op::Exp => Dual {
+                            value: a.value.exp(),
+                            derivative: a.value.exp() * a.derivative,
+                        },
+                        _ => Dual {
+                            value: a.value.ln(),
+                            derivative: a.derivative / a.value,
+                        },
+                    }
+                }
+            };
+
+            stack[len] = result;
+            len += 1;
+        }
+
+        (stack[0].value, stack[0].derivative)
+    }
+
+    // WGSL function `custom_step(z: Dual, c: vec2<f32>) -> Dual` built on the dual helpers of the shader
+    #[must_use]
+    pub fn to_wgsl(&self) -> String {
+        let mut lines = Vec::with_capacity(Self::MAX_LEN + 1);
+        let mut stack = Vec::with_capacity(Self::MAX_LEN);
+
+        for (n, op) in self.ops().iter().enumerate() {
+            let value = match *op {
+                Op::Z => String::from("z"),
+                Op::C => String::from("Dual(c, vec2(1.0, 0.0))"),
+                Op::I => String::from("Dual(vec2(0.0, 1.0), vec2(0.0, 0.0))"),
+                #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+                Op::Constant(constant) => format!("Dual(vec2({:?}, 0.0), vec2(0.0, 0.0))", constant as f32),
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => {
+                    let b = stack.pop().unwrap_or_default();
+                    let a = stack.pop().unwrap_or_default();
+
+                    format!("dual_{}(v{a}, v{b})", op.name())
+                }
+                _ => format!("dual_{}(v{})", op.name(), stack.pop().unwrap_or_default()),
+            };
+
+            lines.push(format!("    let v{n} = {value};"));
+            stack.push(n);
+        }
+
+        lines.push(format!("    return v{};", stack.pop().unwrap_or_default()));
+
+        format!(
+            "fn custom_step(z: Dual, c: vec2<f32>) -> Dual {{\n{}\n}}\n",
+            lines.join("\n")
+        )
+    }
+}
+
+impl Default for Formula {
+    fn default() -> Self {
+        Self::BURNING_SHIP
+    }
+}
+
+impl Display for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const ATOM: u8 = 5;
+
+        // Infix string of each subexpression with the precedence of its outermost operator
+        let mut stack: Vec<(String, u8)> = Vec::with_capacity(Self::MAX_LEN);
+
+        for op in self.ops() {
+            let result = match *op {
+                Op::Z | Op::C | Op::I => (String::from(op.name()), ATOM),
+                Op::Constant(constant) => (format!("{constant}"), ATOM),
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => {
+                    let (b, b_precedence) = stack.pop().unwrap_or_default();
+                    let (a, a_precedence) = stack.pop().unwrap_or_default();
+
+                    let (symbol, precedence) = match *op {
+                        Op::Add => ("+", 1),
+                        Op::Sub => ("-", 1),
+                        Op::Mul => ("*", 2),
+                        Op::Div => ("/", 2),
+                        _ => ("^", 4),
+                    };
+
+                    // `^` is right associative, the rest are left associative
+                    let (a_parens, b_parens) = if precedence == 4 {
+                        (a_precedence <= precedence, b_precedence < precedence)
+                    } else {
+                        (a_precedence < precedence, b_precedence <= precedence)
+                    };
+
+                    let a = if a_parens { format!("({a})") } else { a };
+                    let b = if b_parens { format!("({b})") } else { b };
+
+                    if precedence == 4 {
+                        (format!("{a}^{b}"), precedence)
+                    } else {
+                        (format!("{a} {symbol} {b}"), precedence)
+                    }
+                }
+                Op::Neg => {
+                    let (a, a_precedence) = stack.pop().unwrap_or_default();
+
+                    if a_precedence <= 3 {
+                        (format!("-({a})"), 3)
+                    } else {
+                        (format!("-{a}"), 3)
+                    }
+                }
+                _ => (format!("{}({})", op.name(), stack.pop().unwrap_or_default().0), ATOM),
+            };
+
+            stack.push(result);
+        }
+
+        write!(f, "{}", stack.pop().unwrap_or_default().0)
+    }
+}
+
+// Recursive descent parser emitting the formula in reverse polish notation
+//
+// expression = term (("+" | "-") term)*
+// term       = unary (("*" | "/") unary)*
+// unary      = "-" unary | power
+// power      = atom ("^" unary)?
+// atom       = number | "z" | "c" | "i" | "pi" | "e" | function "(" expression ")" | "(" expression ")"
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    formula: Formula,
+    // Nesting of unary minuses and parentheses, bounded so deep input cannot overflow the stack
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|char| char.is_whitespace()).is_some() {}
+
+        self.chars.peek().copied()
+    }
+
+    fn push(&mut self, op: Op) -> Result<(), &'static str> {
+        if usize::from(self.formula.len) == Formula::MAX_LEN {
+            Err("formula cannot have more than 32 operations")
+        } else {
+            self.formula.ops[usize::from(self.formula.len)] = op;
+            self.formula.len += 1;
+
+            Ok(())
+        }
+    }
+
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<(), &'static str>) -> Result<(), &'static str> {
+        if self.depth == Formula::MAX_LEN {
+            return Err("formula cannot be nested more than 32 levels deep");
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn expression(&mut self) -> Result<(), &'static str> {
+        self.term()?;
+
+        while let Some(char @ ('+' | '-')) = self.peek() {
+            self.chars.next();
+            self.term()?;
+            self.push(if char == '+' { Op::Add } else { Op::Sub })?;
+        }
+
+        Ok(())
+    }
+
+    fn term(&mut self) -> Result<(), &'static str> {
+        self.unary()?;
+
+        while let Some(char @ ('*' | '/')) = self.peek() {
+            self.chars.next();
+            self.unary()?;
+            self.push(if char == '*' { Op::Mul } else { Op::Div })?;
+        }
+
+        Ok(())
+    }
+
+    fn unary(&mut self) -> Result<(), &'static str> {
+        if self.peek() == Some('-') {
+            self.chars.next();
+            self.nested(|parser| {
+                parser.unary()?;
+                parser.push(Op::Neg)
+            })
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<(), &'static str> {
+        self.atom()?;
+
+        if self.peek() == Some('^') {
+            self.chars.next();
+            self.unary()?;
+            self.push(Op::Pow)?;
+        }
+
+        Ok(())
+    }
+
+    fn parenthesized(&mut self) -> Result<(), &'static str> {
+        if self.peek() != Some('(') {
+            return Err("missing opening parenthesis in formula");
+        }
+        self.chars.next();
+
+        self.nested(|parser| {
+            parser.expression()?;
+
+            if parser.peek() == Some(')') {
+                parser.chars.next();
+                Ok(())
+            } else {
+                Err("missing closing parenthesis in formula")
+            }
+        })
+    }
+
+    fn atom(&mut self) -> Result<(), &'static str> {
+        cfg_if! {
+            if #[cfg(feature = "f32")] {
+                use std::f32::consts::{E, PI};
+            } else if #[cfg(feature = "f64")] {
+                use std::f64::consts::{E, PI};
+            }
+        }
+
+        match self.peek() {
+            Some('(') => self.parenthesized(),
+            Some(char) if char.is_ascii_digit() || char == '.' => {
+                let mut number = String::new();
+                while let Some(char) = self.chars.next_if(|char| char.is_ascii_digit() || *char == '.') {
+                    number.push(char);
+                }
+
+                match number.parse::<Float>() {
+                    Ok(constant) if constant.is_finite() => self.push(Op::Constant(constant)),
+                    _ => Err("invalid number in formula"),
+                }
+            }
+            Some(char) if char.is_ascii_alphabetic() => {
+                let mut name = String::new();
+                while let Some(char) = self.chars.next_if(char::is_ascii_alphabetic) {
+                    name.push(char.to_ascii_lowercase());
+                }
+
+                match name.as_str() {
+                    "z" => self.push(Op::Z),
+                    "c" => self.push(Op::C),
+                    "i" => self.push(Op::I),
+                    "pi" => self.push(Op::Constant(PI)),
+                    "e" => self.push(Op::Constant(E)),
+                    name => {
+                        let function = Op::function(name).ok_or("unknown function or variable in formula")?;
+
+                        self.parenthesized()?;
+                        self.push(function)
+                    }
+                }
+            }
+            Some(_) => Err("unexpected character in formula"),
+            None => Err("unexpected end of formula"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Formula::new("abs(z)^2 + c"), Ok(Formula::BURNING_SHIP));
+        assert_eq!(Formula::new("z^2+c").map(|formula| formula.len()), Ok(5));
+        assert_eq!(
+            Formula::new("2^3^2").map(|formula| formula.ops().to_vec()),
+            Ok(vec![
+                Op::Constant(2.0),
+                Op::Constant(3.0),
+                Op::Constant(2.0),
+                Op::Pow,
+                Op::Pow
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Formula::new("").is_err());
+        assert!(Formula::new("z^2 +").is_err());
+        assert!(Formula::new("(z").is_err());
+        assert!(Formula::new("z c").is_err());
+        assert!(Formula::new("x + c").is_err());
+        assert!(Formula::new(&format!("{}c", "z + ".repeat(16))).is_err());
+        assert!(Formula::new(&("-".repeat(200_000) + "z")).is_err());
+        assert!(Formula::new(&format!("{}z{}", "(".repeat(200_000), ")".repeat(200_000))).is_err());
+        assert!(Formula::new(&format!("{}z{}", "sin(".repeat(200_000), ")".repeat(200_000))).is_err());
+        assert_eq!(
+            Formula::new(&format!("{}z{}", "(".repeat(32), ")".repeat(32))),
+            Formula::new("z")
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for formula in [
+            "z^2 + c",
+            "abs(z)^2 + c",
+            "conj(z)^2 + c",
+            "(z - c) * (z + c) / (z - 1)",
+            "z - (c - 1)",
+            "2^3^2",
+            "(2^3)^2",
+            "-z^2 + c",
+            "-(z + c)",
+            "sin(z) * c + exp(-z) - log(c) + cos(i * 0.5)",
+        ] {
+            let parsed = Formula::new(formula).unwrap();
+
+            assert_eq!(parsed.to_string(), formula);
+            assert_eq!(Formula::new(&parsed.to_string()), Ok(parsed));
+        }
+
+        // Named constants are stored as their value
+        let parsed = Formula::new("z^pi + e").unwrap();
+        assert_eq!(Formula::new(&parsed.to_string()), Ok(parsed));
+    }
+
+    #[test]
+    fn evaluate() {
+        let formula = Formula::new("z^2 + c").unwrap();
+        let (z, dz) = formula.evaluate(
+            Complex::new(1.0, 2.0),
+            Complex::new(1.0, 0.0),
+            Complex::new(0.5, 0.0),
+            Complex::new(0.0, 0.0),
+        );
+
+        assert!((z - Complex::new(-2.5, 4.0)).norm() < 1e-5);
+        assert!((dz - Complex::new(2.0, 4.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn to_wgsl() {
+        assert_eq!(
+            Formula::new("z^2 + c").unwrap().to_wgsl(),
+            "fn custom_step(z: Dual, c: vec2<f32>) -> Dual {
+    let v0 = z;
+    let v1 = Dual(vec2(2.0, 0.0), vec2(0.0, 0.0));
+    let v2 = dual_pow(v0, v1);
+    let v3 = Dual(c, vec2(1.0, 0.0));
+    let v4 = dual_add(v2, v3);
+    return v4;
+}
+"
+        );
+    }
+}
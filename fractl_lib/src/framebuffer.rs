@@ -1,12 +1,15 @@
 use std::{
+    fmt::Display,
     num::NonZeroU32,
     ops::{Add, Deref, DerefMut, Index, IndexMut},
 };
 
 use cgmath::Vector2;
 #[cfg(feature = "image")]
-use image::{Rgb, RgbImage};
+use image::{ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage};
 
+// The alpha is stored inverted in the highest byte, so opaque colors keep it at 0 like softbuffer and the compute
+// shader expect
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Color(u32);
@@ -17,10 +20,16 @@ impl Color {
     pub const RED: Self = Self::new(255, 0, 0);
     pub const GREEN: Self = Self::new(0, 255, 0);
     pub const BLUE: Self = Self::new(0, 0, 255);
+    pub const TRANSPARENT: Self = Self::new_rgba(0, 0, 0, 0);
 
     #[must_use]
     pub const fn new(red: u8, green: u8, blue: u8) -> Self {
-        Self((blue as u32) | ((green as u32) << 8) | ((red as u32) << 16))
+        Self::new_rgba(red, green, blue, 255)
+    }
+
+    #[must_use]
+    pub const fn new_rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Self((blue as u32) | ((green as u32) << 8) | ((red as u32) << 16) | (((255 - alpha) as u32) << 24))
     }
 
     #[must_use]
@@ -38,13 +47,66 @@ impl Color {
         (self.0 & 0x00_00_FF) as u8
     }
 
+    #[must_use]
+    pub const fn alpha(&self) -> u8 {
+        255 - (self.0 >> 24) as u8
+    }
+
+    #[must_use]
+    pub const fn with_alpha(&self, alpha: u8) -> Self {
+        Self::new_rgba(self.red(), self.green(), self.blue(), alpha)
+    }
+
+    #[must_use]
+    pub const fn is_opaque(&self) -> bool {
+        self.alpha() == 255
+    }
+
+    // Porter-Duff source over, `self` on top of `background`
+    // https://en.wikipedia.org/wiki/Alpha_compositing
+    #[must_use]
+    pub fn over(&self, background: Self) -> Self {
+        self.blend(background, BlendMode::Normal)
+    }
+
+    // Mixes `self` with the color under it by `mode`, then composites the result over it
+    // https://www.w3.org/TR/compositing-1/#generalformula
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn blend(&self, backdrop: Self, mode: BlendMode) -> Self {
+        let (source_alpha, backdrop_alpha) = (f32::from(self.alpha()) / 255.0, f32::from(backdrop.alpha()) / 255.0);
+        let alpha = backdrop_alpha.mul_add(1.0 - source_alpha, source_alpha);
+
+        if alpha == 0.0 {
+            return Self::TRANSPARENT;
+        }
+
+        let blend_channel = |source: u8, backdrop: u8| {
+            let (source, backdrop) = (f32::from(source) / 255.0, f32::from(backdrop) / 255.0);
+            // Where the backdrop is transparent the source is left as it is
+            let source = (1.0 - backdrop_alpha) * source + backdrop_alpha * mode.blend(backdrop, source);
+
+            ((source * source_alpha + backdrop * backdrop_alpha * (1.0 - source_alpha)) / alpha * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+
+        Self::new_rgba(
+            blend_channel(self.red(), backdrop.red()),
+            blend_channel(self.green(), backdrop.green()),
+            blend_channel(self.blue(), backdrop.blue()),
+            (alpha * 255.0).round() as u8,
+        )
+    }
+
     #[allow(clippy::missing_errors_doc, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     pub fn scale(&self, times: f32) -> Result<Self, &'static str> {
         if times.is_finite() && (0.0..=1.0).contains(&times) {
-            Ok(Self::new(
+            Ok(Self::new_rgba(
                 (f32::from(self.red()) * times) as u8,
                 (f32::from(self.green()) * times) as u8,
                 (f32::from(self.blue()) * times) as u8,
+                self.alpha(),
             ))
         } else {
             Err("times must be a normal f32 between 0 and 1.0 (inclusive)")
@@ -53,7 +115,7 @@ impl Color {
 
     #[must_use]
     pub const fn invert(&self) -> Self {
-        Self::new(255 - self.red(), 255 - self.green(), 255 - self.blue())
+        Self::new_rgba(255 - self.red(), 255 - self.green(), 255 - self.blue(), self.alpha())
     }
 }
 
@@ -81,15 +143,193 @@ impl Add for Color {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self::new(
+        Self::new_rgba(
             self.red().saturating_add(rhs.red()),
             self.green().saturating_add(rhs.green()),
             self.blue().saturating_add(rhs.blue()),
+            self.alpha().max(rhs.alpha()),
         )
     }
 }
 
-#[derive(Clone, Debug, Hash)]
+// How a color is mixed with the one under it before compositing, channels are between 0 and 1
+// https://www.w3.org/TR/compositing-1/#blending
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+}
+
+impl BlendMode {
+    const NUM_OF_VARIANTS: u8 = 4;
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Normal => 0,
+            Self::Multiply => 1,
+            Self::Screen => 2,
+            Self::Overlay => 3,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Normal,
+            1 => Self::Multiply,
+            2 => Self::Screen,
+            3 => Self::Overlay,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self::from_id(self.id() + 1)
+    }
+
+    #[must_use]
+    pub const fn prev(&self) -> Self {
+        Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
+    }
+
+    #[must_use]
+    pub fn blend(&self, backdrop: f32, source: f32) -> f32 {
+        let screen = |a: f32, b: f32| a + b - a * b;
+
+        match self {
+            Self::Normal => source,
+            Self::Multiply => backdrop * source,
+            Self::Screen => screen(backdrop, source),
+            // Hard light with the layers swapped
+            Self::Overlay => {
+                if backdrop <= 0.5 {
+                    source * 2.0 * backdrop
+                } else {
+                    screen(source, 2.0f32.mul_add(backdrop, -1.0))
+                }
+            }
+        }
+    }
+}
+
+impl Display for BlendMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlendMode::Normal => write!(f, "Normal"),
+            BlendMode::Multiply => write!(f, "Multiply"),
+            BlendMode::Screen => write!(f, "Screen"),
+            BlendMode::Overlay => write!(f, "Overlay"),
+        }?;
+
+        Ok(())
+    }
+}
+
+// Linear light RGB, what colorings produce before it gets quantized, channels can go above 1.0 for HDR
+// The alpha is not premultiplied
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearColor {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32,
+}
+
+impl LinearColor {
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0);
+    pub const TRANSPARENT: Self = Self::BLACK.with_alpha(0.0);
+
+    #[must_use]
+    pub const fn new(red: f32, green: f32, blue: f32) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha: 1.0,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_alpha(self, alpha: f32) -> Self {
+        Self { alpha, ..self }
+    }
+
+    // From gamma encoded sRGB channels, 1.0 being the brightest displayable value
+    #[must_use]
+    pub fn from_srgb(red: f32, green: f32, blue: f32) -> Self {
+        Self::new(srgb_to_linear(red), srgb_to_linear(green), srgb_to_linear(blue))
+    }
+
+    // Gamma encoded sRGB channels, not clamped
+    #[must_use]
+    pub fn to_srgb(&self) -> [f32; 3] {
+        [
+            linear_to_srgb(self.red),
+            linear_to_srgb(self.green),
+            linear_to_srgb(self.blue),
+        ]
+    }
+
+    #[must_use]
+    pub fn to_color(&self) -> Color {
+        self.to_color_dithered(0.0)
+    }
+
+    // `threshold` between -0.5 and 0.5 is added before rounding, in steps of the 8-bit output
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn to_color_dithered(&self, threshold: f32) -> Color {
+        let [red, green, blue] = self
+            .to_srgb()
+            .map(|channel| channel.mul_add(255.0, threshold).round().clamp(0.0, 255.0) as u8);
+
+        Color::new_rgba(red, green, blue, (self.alpha * 255.0).round().clamp(0.0, 255.0) as u8)
+    }
+
+    // Gamma encoded like 8-bit colors, which is what 16-bit PNGs without color information are assumed to be, the
+    // alpha stays linear
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn to_rgba16(&self) -> [u16; 4] {
+        let [red, green, blue] = self.to_srgb();
+
+        [red, green, blue, self.alpha].map(|channel| (channel * 65535.0).round().clamp(0.0, 65535.0) as u16)
+    }
+}
+
+impl Default for LinearColor {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl From<Color> for LinearColor {
+    fn from(color: Color) -> Self {
+        Self::from_srgb(
+            f32::from(color.red()) / 255.0,
+            f32::from(color.green()) / 255.0,
+            f32::from(color.blue()) / 255.0,
+        )
+        .with_alpha(f32::from(color.alpha()) / 255.0)
+    }
+}
+
+impl Add for LinearColor {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.red + rhs.red, self.green + rhs.green, self.blue + rhs.blue)
+            .with_alpha(self.alpha.max(rhs.alpha))
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct FrameBuffer {
     pub(crate) data: Vec<Color>,
     size: Vector2<NonZeroU32>,
@@ -122,12 +362,39 @@ impl FrameBuffer {
         unsafe { transform_vec::<Color, u32>(self.data) }
     }
 
+    #[must_use]
+    pub fn is_opaque(&self) -> bool {
+        self.data.iter().all(Color::is_opaque)
+    }
+
+    // Blends `color` into the pixel at `pos`
+    pub fn blend_pixel(&mut self, pos: Vector2<u32>, color: Color, mode: BlendMode) {
+        self[pos] = color.blend(self[pos], mode);
+    }
+
+    // Blends `layer` on top with its top left corner at `pos`, the parts outside of this buffer are cut off
+    pub fn composite(&mut self, layer: &FrameBuffer, pos: Vector2<u32>, mode: BlendMode) {
+        // Size of the part of the layer that fits
+        let size = layer.size().zip(
+            self.size.zip(pos, |size, pos| size.get().saturating_sub(pos)),
+            |layer_size, space| layer_size.get().min(space),
+        );
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let layer_pos = Vector2::new(x, y);
+                self.blend_pixel(pos + layer_pos, layer[layer_pos], mode);
+            }
+        }
+    }
+
     #[cfg(feature = "image")]
+    #[must_use]
     pub fn as_image(&self) -> RgbImage {
-        let mut img = RgbImage::new(self.size().x, self.size().y);
+        let mut img = RgbImage::new(self.size().x.get(), self.size().y.get());
 
-        for x in 0..self.size().x {
-            for y in 0..self.size().y {
+        for x in 0..self.size().x.get() {
+            for y in 0..self.size().y.get() {
                 let color = self[Vector2::new(x, y)];
                 img.put_pixel(x, y, Rgb([color.red(), color.green(), color.blue()]));
             }
@@ -136,6 +403,16 @@ impl FrameBuffer {
         img
     }
 
+    // Keeps the alpha, for overlaying renders on something else
+    #[cfg(feature = "image")]
+    #[must_use]
+    pub fn as_rgba_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.size().x.get(), self.size().y.get(), |x, y| {
+            let color = self[Vector2::new(x, y)];
+            Rgba([color.red(), color.green(), color.blue(), color.alpha()])
+        })
+    }
+
     #[must_use]
     pub fn size(&self) -> &Vector2<NonZeroU32> {
         &self.size
@@ -175,10 +452,131 @@ impl IndexMut<Vector2<u32>> for FrameBuffer {
     }
 }
 
+// Keeps the full precision of the colorings, quantized only when converted for output
+#[derive(Clone, Debug, PartialEq)]
+pub struct HdrFrameBuffer {
+    pub(crate) data: Vec<LinearColor>,
+    size: Vector2<NonZeroU32>,
+}
+
+impl HdrFrameBuffer {
+    // 4x4 Bayer matrix, spreads the rounding error so gradients do not band
+    // https://en.wikipedia.org/wiki/Ordered_dithering
+    const BAYER_MATRIX: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+    pub fn new(size: impl Into<Vector2<NonZeroU32>>) -> Self {
+        let size = size.into();
+
+        Self {
+            data: vec![LinearColor::default(); (size.x.get() * size.y.get()) as usize],
+            size,
+        }
+    }
+
+    // 8-bit sRGB, optionally with ordered dithering
+    #[must_use]
+    pub fn to_framebuffer(&self, dither: bool) -> FrameBuffer {
+        let mut framebuffer = FrameBuffer::new(self.size);
+
+        framebuffer.data = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                if dither {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let pos = self.index_to_pos(index as u32);
+                    let threshold = Self::BAYER_MATRIX[(pos.y % 4) as usize][(pos.x % 4) as usize];
+
+                    color.to_color_dithered((f32::from(threshold) + 0.5) / 16.0 - 0.5)
+                } else {
+                    color.to_color()
+                }
+            })
+            .collect();
+
+        framebuffer
+    }
+
+    // Red, green and blue of every pixel, row by row
+    #[must_use]
+    pub fn rgb16(&self) -> Vec<u16> {
+        self.data
+            .iter()
+            .flat_map(|color| {
+                let [red, green, blue, _] = color.to_rgba16();
+                [red, green, blue]
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn rgba16(&self) -> Vec<u16> {
+        self.data.iter().flat_map(LinearColor::to_rgba16).collect()
+    }
+
+    #[must_use]
+    pub fn is_opaque(&self) -> bool {
+        self.data.iter().all(|color| color.alpha >= 1.0)
+    }
+
+    #[cfg(feature = "image")]
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn as_image16(&self) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        ImageBuffer::from_raw(self.size.x.get(), self.size.y.get(), self.rgb16()).unwrap()
+    }
+
+    #[must_use]
+    pub fn size(&self) -> &Vector2<NonZeroU32> {
+        &self.size
+    }
+
+    #[must_use]
+    pub fn pos_to_index(&self, buffer_pos: Vector2<u32>) -> u32 {
+        buffer_pos.y * self.size.x.get() + buffer_pos.x
+    }
+
+    #[must_use]
+    pub fn index_to_pos(&self, index: u32) -> Vector2<u32> {
+        let x = index % self.size.x.get();
+        let y = (index - x) / self.size.x.get();
+
+        Vector2::new(x, y)
+    }
+}
+
+impl Index<Vector2<u32>> for HdrFrameBuffer {
+    type Output = LinearColor;
+
+    fn index(&self, index: Vector2<u32>) -> &Self::Output {
+        assert!((index.x < self.size.x.get()) && (index.y < self.size.y.get()));
+
+        let index = self.pos_to_index(index) as usize;
+        &self.data[index]
+    }
+}
+
+impl IndexMut<Vector2<u32>> for HdrFrameBuffer {
+    fn index_mut(&mut self, index: Vector2<u32>) -> &mut Self::Output {
+        assert!((index.x < self.size.x.get()) && (index.y < self.size.y.get()));
+
+        let index = self.pos_to_index(index) as usize;
+        &mut self.data[index]
+    }
+}
+
 pub trait Draw {
     fn draw(&self, pos: Vector2<u32>, buffer: &mut FrameBuffer);
 }
 
+// Composites over the buffer like a layer with the normal blend mode
+impl Draw for FrameBuffer {
+    fn draw(&self, pos: Vector2<u32>, buffer: &mut FrameBuffer) {
+        buffer.composite(self, pos, BlendMode::Normal);
+    }
+}
+
 pub trait Fill {
     fn fill(&self, buffer: &mut FrameBuffer);
 }
@@ -194,3 +592,20 @@ pub(crate) unsafe fn transform_vec<T, S>(mut v: Vec<T>) -> Vec<S> {
 
     Vec::from_raw_parts(ptr, len, capacity)
 }
+
+// https://en.wikipedia.org/wiki/SRGB
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.040_45 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(2.4_f32.recip()) - 0.055
+    }
+}
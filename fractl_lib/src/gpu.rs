@@ -1,6 +1,6 @@
 #![allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
 
-use crate::{Camera, ColorType, FractalType};
+use crate::{Bailout, Camera, ColorType, Formula, FractalType};
 
 use std::{cell::RefCell, num::NonZeroU32, sync::Mutex};
 
@@ -12,7 +12,8 @@ pub(crate) fn do_gpu_compute(
     camera: &Camera,
     screen_size: impl Into<Vector2<NonZeroU32>>,
     max_iterations: NonZeroU32,
-    selected_fractal: FractalType,
+    bailout: Bailout,
+    selected_fractal: &FractalType,
     selected_color: ColorType,
 ) {
     static INSTANCE: Mutex<Option<WgpuContext>> = Mutex::new(None);
@@ -29,7 +30,14 @@ pub(crate) fn do_gpu_compute(
     }
 
     if let Some(context) = &mut *instance_lock {
-        context.update(camera, screen_size, max_iterations, selected_fractal, selected_color);
+        context.update(
+            camera,
+            screen_size,
+            max_iterations,
+            bailout,
+            selected_fractal,
+            selected_color,
+        );
 
         let mut chunks = io_buffer.chunks_exact_mut(context.buffer_len());
 
@@ -77,6 +85,14 @@ struct ArgsUniform {
     selected_fractal: u32,
     selected_color: u32,
     multi_exponent: f32,
+    lyapunov_sequence: [u32; 2],
+    lyapunov_sequence_len: u32,
+    _padding: u32,
+    complex_parametr: [f32; 2],
+    bailout_radius: f32,
+    bailout_norm: u32,
+    rotation: f32,
+    projection: u32,
 }
 
 impl ArgsUniform {
@@ -85,7 +101,8 @@ impl ArgsUniform {
         camera: &Camera,
         screen_size: impl Into<Vector2<NonZeroU32>>,
         max_iterations: NonZeroU32,
-        selected_fractal: FractalType,
+        bailout: Bailout,
+        selected_fractal: &FractalType,
         selected_color: ColorType,
     ) -> Self {
         Self {
@@ -95,8 +112,25 @@ impl ArgsUniform {
             center_pos: camera.center_pos.map(|x| x as f32).into(),
             max_iterations: max_iterations.get(),
             selected_fractal: u32::from(selected_fractal.id()),
-            selected_color: selected_color as u32,
+            selected_color: u32::from(selected_color.id()),
             multi_exponent: selected_fractal.multi_parametr().unwrap_or(0.0),
+            lyapunov_sequence: match *selected_fractal {
+                FractalType::Lyapunov(sequence) => [sequence.bits() as u32, (sequence.bits() >> 32) as u32],
+                _ => [0, 0],
+            },
+            lyapunov_sequence_len: match *selected_fractal {
+                FractalType::Lyapunov(sequence) => u32::from(sequence.len()),
+                _ => 1,
+            },
+            _padding: 0,
+            complex_parametr: match *selected_fractal {
+                FractalType::Phoenix(parametr) | FractalType::Nova(parametr) => [parametr.re, parametr.im],
+                _ => [0.0, 0.0],
+            },
+            bailout_radius: bailout.radius(),
+            bailout_norm: u32::from(bailout.norm().id()),
+            rotation: camera.rotation as f32,
+            projection: u32::from(camera.projection.id()),
         }
     }
 }
@@ -105,7 +139,10 @@ struct WgpuContext {
     device: wgpu::Device,
     queue: wgpu::Queue,
     args_buffer: wgpu::Buffer,
+    pipeline_layout: wgpu::PipelineLayout,
     pipeline: wgpu::ComputePipeline,
+    // Formula compiled into the current pipeline
+    formula: Formula,
     bind_group: wgpu::BindGroup,
     storage_buffer: wgpu::Buffer,
     output_staging_buffer: wgpu::Buffer,
@@ -117,11 +154,19 @@ impl WgpuContext {
         camera: &Camera,
         screen_size: impl Into<Vector2<NonZeroU32>>,
         max_iterations: NonZeroU32,
-        selected_fractal: FractalType,
+        bailout: Bailout,
+        selected_fractal: &FractalType,
         selected_color: ColorType,
     ) {
         let screen_size = screen_size.into();
 
+        if let FractalType::Custom(formula) = selected_fractal {
+            if *formula != self.formula {
+                self.pipeline = Self::create_pipeline(&self.device, &self.pipeline_layout, formula);
+                self.formula = *formula;
+            }
+        }
+
         self.queue.write_buffer(
             &self.args_buffer,
             0,
@@ -129,12 +174,35 @@ impl WgpuContext {
                 camera,
                 screen_size,
                 max_iterations,
+                bailout,
                 selected_fractal,
                 selected_color,
             )]),
         );
     }
 
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        formula: &Formula,
+    ) -> wgpu::ComputePipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Owned(format!(
+                "{}\n{}",
+                include_str!("./shader.wgsl"),
+                formula.to_wgsl()
+            ))),
+        });
+
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        })
+    }
+
     fn new() -> Self {
         pollster::block_on(Self::new_async())
     }
@@ -163,11 +231,6 @@ impl WgpuContext {
 
         let buffer_len = device.limits().max_compute_workgroups_per_dimension;
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(include_str!("./shader.wgsl"))),
-        });
-
         let args_uniform = ArgsUniform::default();
         let args_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Args Buffer"),
@@ -233,18 +296,16 @@ impl WgpuContext {
             bind_group_layouts: &[&bind_group_layout],
             push_constant_ranges: &[],
         });
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: "main",
-        });
+        let formula = Formula::default();
+        let pipeline = Self::create_pipeline(&device, &pipeline_layout, &formula);
 
         Self {
             device,
             queue,
             args_buffer,
+            pipeline_layout,
             pipeline,
+            formula,
             bind_group,
             storage_buffer,
             output_staging_buffer,
@@ -0,0 +1,130 @@
+use std::{fmt::Display, num::NonZeroU32};
+
+use cgmath::Vector2;
+
+use crate::{
+    float,
+    framebuffer::{Color, LinearColor},
+    Float,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interior {
+    pub z: Vector2<Float>,
+    /// Period of the attracting cycle the orbit converged to, if one was found
+    pub period: Option<NonZeroU32>,
+    /// Estimated distance (in world units) to the boundary of the set
+    pub distance: Option<Float>,
+}
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteriorColorType {
+    Solid(Color),
+    FinalMagnitude,
+    Period,
+    Distance,
+}
+
+impl InteriorColorType {
+    const NUM_OF_VARIANTS: u8 = 4;
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Solid(_) => 0,
+            Self::FinalMagnitude => 1,
+            Self::Period => 2,
+            Self::Distance => 3,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Solid(Color::BLACK),
+            1 => Self::FinalMagnitude,
+            2 => Self::Period,
+            3 => Self::Distance,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self::from_id(self.id() + 1)
+    }
+
+    #[must_use]
+    pub const fn prev(&self) -> Self {
+        Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
+    }
+
+    #[must_use]
+    pub const fn needs_orbit(&self) -> bool {
+        !matches!(self, Self::Solid(_))
+    }
+
+    #[must_use]
+    pub fn interior_color(&self, interior: Option<&Interior>, pixel_size: Float) -> LinearColor {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::unnecessary_cast)]
+        match (self, interior) {
+            (Self::Solid(color), _) => LinearColor::from(*color),
+            (Self::FinalMagnitude, Some(interior)) => {
+                let magnitude = (interior.z.x.hypot(interior.z.y) / 2.0).clamp(0.0, 1.0);
+
+                LinearColor::from_srgb(0.0, (magnitude * 128.0 / 255.0) as f32, magnitude as f32)
+            }
+            (
+                Self::Period,
+                Some(Interior {
+                    period: Some(period), ..
+                }),
+            ) => {
+                // Spread neighbouring periods far apart on the OLC palette
+                let n = float(period.get());
+                let a = 2.4;
+
+                LinearColor::from_srgb(
+                    (0.5 * (a * n).sin() + 0.5) as f32,
+                    (0.5 * (a * n + 2.094).sin() + 0.5) as f32,
+                    (0.5 * (a * n + 4.188).sin() + 0.5) as f32,
+                )
+            }
+            (
+                Self::Distance,
+                Some(Interior {
+                    distance: Some(distance),
+                    ..
+                }),
+            ) => {
+                let brightness = 1.0 - (-distance / (32.0 * pixel_size)).exp();
+
+                LinearColor::from_srgb(0.0, brightness as f32, brightness as f32)
+            }
+            _ => LinearColor::BLACK,
+        }
+    }
+}
+
+impl Default for InteriorColorType {
+    fn default() -> Self {
+        Self::Solid(Color::BLACK)
+    }
+}
+
+impl Display for InteriorColorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InteriorColorType::Solid(color) if color.alpha() == 0 => write!(f, "Transparent"),
+            InteriorColorType::Solid(color) => {
+                write!(f, "Solid ({:}, {:}, {:})", color.red(), color.green(), color.blue())
+            }
+            InteriorColorType::FinalMagnitude => write!(f, "Final magnitude"),
+            InteriorColorType::Period => write!(f, "Period"),
+            InteriorColorType::Distance => write!(f, "Distance"),
+        }?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+use std::{any::Any, fmt::Debug, num::NonZeroU32};
+
+use cgmath::Vector2;
+use num_complex::Complex;
+
+use crate::{
+    average::OrbitAverageTracker, float, trap::OrbitTrapTracker, Bailout, EscapeTime, Float, Interior, OrbitAverage,
+    OrbitTrap,
+};
+
+pub(crate) const MAX_PERIOD: u32 = 1024;
+
+/// Lets `Fractal` downcast its formula back to a `FractalType`, implemented for every type
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Iteration z = f(z, c) that `Fractal` can be built from, the built-in `FractalType`s are implemented through it
+pub trait IterationFormula: AsAny + Debug + Send + Sync {
+    /// Name shown in the UI
+    fn name(&self) -> &str;
+
+    /// Named parameters of the formula, e.g. the exponent of the Multibrot set
+    fn parameters(&self) -> Vec<(&str, Float)> {
+        Vec::new()
+    }
+
+    /// Starting z of the orbit of c
+    fn init(&self, c: Complex<Float>) -> Complex<Float>;
+
+    /// dz/dc of `init`, zero for a constant starting point, formulas whose starting point depends on c must override
+    /// it or their distance estimates will be wrong
+    fn init_derivative(&self, _c: Complex<Float>) -> Complex<Float> {
+        Complex::new(0.0, 0.0)
+    }
+
+    /// `previous_z` is z of the iteration before, zero before the first step
+    fn step(&self, z: Complex<Float>, previous_z: Complex<Float>, c: Complex<Float>) -> Complex<Float>;
+
+    /// Default Euclidean bailout radius, `None` for formulas whose orbits never escape, `Fractal` can override it
+    /// together with the norm
+    fn bailout(&self) -> Option<Float>;
+
+    /// Degree of the formula in z, used for smooth iteration counts
+    fn degree(&self) -> Float {
+        2.0
+    }
+
+    /// (df/dz, df/dc) ignoring `previous_z`, distances are only estimated for formulas that provide them
+    fn derivatives(&self, _z: Complex<Float>, _c: Complex<Float>) -> Option<(Complex<Float>, Complex<Float>)> {
+        None
+    }
+
+    /// Orbits whose steps get shorter than this are considered converged to a fixed point
+    fn convergence_tolerance(&self) -> Option<Float> {
+        None
+    }
+
+    /// Iterates the orbit of `world_pos` until it escapes, converges or runs out of iterations
+    fn escape_time(
+        &self,
+        world_pos: Vector2<Float>,
+        max_iterations: NonZeroU32,
+        bailout: Bailout,
+        orbit_trap: Option<&OrbitTrap>,
+        orbit_average: Option<OrbitAverage>,
+    ) -> EscapeTime {
+        iterate(
+            self,
+            world_pos,
+            max_iterations.get(),
+            bailout,
+            orbit_trap,
+            orbit_average,
+        )
+    }
+
+    /// Final z and period of orbits that never escaped
+    fn interior(&self, world_pos: Vector2<Float>, max_iterations: NonZeroU32) -> Interior {
+        let (z, period) = periodicity(self, Complex::new(world_pos.x, world_pos.y), max_iterations);
+
+        Interior {
+            z: Vector2::new(z.re, z.im),
+            period,
+            distance: None,
+        }
+    }
+}
+
+// Iterates until the orbit escapes the bailout radius, converges or runs out of iterations
+pub(crate) fn iterate<F: IterationFormula + ?Sized>(
+    formula: &F,
+    world_pos: Vector2<Float>,
+    max_iterations: u32,
+    bailout: Bailout,
+    orbit_trap: Option<&OrbitTrap>,
+    orbit_average: Option<OrbitAverage>,
+) -> EscapeTime {
+    let c = Complex::new(world_pos.x, world_pos.y);
+    let convergence_tolerance = formula.convergence_tolerance();
+    let mut orbit_trap_tracker = orbit_trap.map(OrbitTrapTracker::new);
+    let mut orbit_average_tracker =
+        orbit_average.map(|orbit_average| OrbitAverageTracker::new(orbit_average, world_pos, formula.degree()));
+
+    let mut n = 0;
+    let (mut z, mut previous_z) = (formula.init(c), Complex::new(0.0, 0.0));
+    // dz/dc
+    let mut dz = Some(formula.init_derivative(c));
+    let (mut step, mut previous_step) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+    let mut is_converged = false;
+
+    while !is_converged && bailout.contains(Vector2::new(z.re, z.im)) && (n < max_iterations) {
+        dz = dz.zip(formula.derivatives(z, c)).map(|(dz, (f_z, f_c))| f_z * dz + f_c);
+
+        let next_z = formula.step(z, previous_z, c);
+        (step, previous_step) = (next_z - z, step);
+        (z, previous_z) = (next_z, z);
+
+        if let Some(orbit_trap_tracker) = &mut orbit_trap_tracker {
+            orbit_trap_tracker.update(Vector2::new(z.re, z.im));
+        }
+        if let Some(orbit_average_tracker) = &mut orbit_average_tracker {
+            orbit_average_tracker.update(Vector2::new(z.re, z.im));
+        }
+
+        n += 1;
+        is_converged = convergence_tolerance.is_some_and(|tolerance| step.norm() < tolerance);
+    }
+
+    let mut escape_time = EscapeTime::new(
+        n,
+        max_iterations,
+        Vector2::new(z.re, z.im),
+        dz.map_or(Vector2::new(0.0, 0.0), |dz| Vector2::new(dz.re, dz.im)),
+        formula.degree(),
+        bailout,
+    );
+
+    if dz.is_none() || is_converged {
+        escape_time.distance = 0.0;
+    }
+
+    if let (true, Some(tolerance)) = (is_converged, convergence_tolerance) {
+        // Interpolates the (linear) convergence of the last steps
+        let fraction = (tolerance / step.norm()).ln() / (previous_step.norm() / step.norm()).ln();
+
+        escape_time.converged = true;
+        escape_time.smooth_iterations = float(n)
+            - if fraction.is_finite() {
+                fraction.clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+    }
+
+    escape_time
+        .with_orbit_trap(orbit_trap_tracker)
+        .with_orbit_average(orbit_average_tracker)
+}
+
+// Final z of the orbit and the period of the cycle it converged to
+// https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Periodicity_checking
+pub(crate) fn periodicity<F: IterationFormula + ?Sized>(
+    formula: &F,
+    c: Complex<Float>,
+    max_iterations: NonZeroU32,
+) -> (Complex<Float>, Option<NonZeroU32>) {
+    let (mut z, mut previous_z) = (formula.init(c), Complex::new(0.0, 0.0));
+
+    for _ in 0..max_iterations.get() {
+        (z, previous_z) = (formula.step(z, previous_z, c), z);
+    }
+
+    let tolerance = Float::EPSILON.sqrt() * z.norm().max(1.0);
+    let (mut z_period, mut previous_z_period) = (z, previous_z);
+
+    let period = (1..=MAX_PERIOD.min(max_iterations.get()))
+        .find(|_| {
+            (z_period, previous_z_period) = (formula.step(z_period, previous_z_period, c), z_period);
+            (z_period - z).norm() < tolerance
+        })
+        .and_then(NonZeroU32::new);
+
+    (z, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{Camera, ColorType, Fractal, FractalType};
+
+    // z^2 + c starting from z = c, like the Multibrot set
+    #[derive(Debug)]
+    struct StartAtC;
+
+    impl IterationFormula for StartAtC {
+        fn name(&self) -> &'static str {
+            "Start at c"
+        }
+
+        fn init(&self, c: Complex<Float>) -> Complex<Float> {
+            c
+        }
+
+        fn init_derivative(&self, _c: Complex<Float>) -> Complex<Float> {
+            Complex::new(1.0, 0.0)
+        }
+
+        fn step(&self, z: Complex<Float>, _previous_z: Complex<Float>, c: Complex<Float>) -> Complex<Float> {
+            z * z + c
+        }
+
+        fn bailout(&self) -> Option<Float> {
+            Some(2.0)
+        }
+
+        fn derivatives(&self, z: Complex<Float>, _c: Complex<Float>) -> Option<(Complex<Float>, Complex<Float>)> {
+            Some((2.0 * z, Complex::new(1.0, 0.0)))
+        }
+    }
+
+    #[test]
+    fn init_derivative() {
+        let c = Complex::new(0.0, 0.0);
+
+        assert_eq!(StartAtC.init_derivative(c), Complex::new(1.0, 0.0));
+        assert_eq!(FractalType::Multibrot(3.0).init_derivative(c), Complex::new(1.0, 0.0));
+        assert_eq!(
+            FractalType::Custom(crate::Formula::default()).init_derivative(c),
+            Complex::new(1.0, 0.0)
+        );
+        assert_eq!(FractalType::Mandelbrot.init_derivative(c), Complex::new(0.0, 0.0));
+        assert_eq!(
+            FractalType::Nova(Complex::new(1.0, 0.0)).init_derivative(c),
+            Complex::new(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn distance_starting_from_c() {
+        let max_iterations = NonZeroU32::new(64).unwrap();
+        let bailout = Bailout::new(2.0, crate::BailoutNorm::Euclidean).unwrap();
+        let world_pos = Vector2::new(0.4, 0.6);
+
+        let escape_time = StartAtC.escape_time(world_pos, max_iterations, bailout, None, None);
+        let expected = FractalType::Multibrot(2.0).escape_time(world_pos, max_iterations, bailout, None, None);
+
+        assert_eq!(escape_time.iterations, expected.iterations);
+        assert!(expected.distance > 0.0);
+        assert!((escape_time.distance - expected.distance).abs() < expected.distance * 1e-3);
+    }
+
+    #[test]
+    fn fractal_type_downcast() {
+        let camera = Camera::new(crate::screen_size(8, 8));
+        let max_iterations = NonZeroU32::new(64).unwrap();
+
+        let fractal = Fractal::new(
+            FractalType::Nova(Complex::new(1.0, 0.0)),
+            ColorType::default(),
+            camera.clone(),
+            max_iterations,
+        );
+        assert_eq!(fractal.fractal_type(), Some(FractalType::Nova(Complex::new(1.0, 0.0))));
+
+        let fractal = Fractal::from_formula(Arc::new(StartAtC), ColorType::default(), camera, max_iterations);
+        assert_eq!(fractal.fractal_type(), None);
+    }
+}
@@ -1,19 +1,54 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::too_many_lines)]
 
+mod animation;
+mod average;
+mod bailout;
+mod buddhabrot;
 mod camera;
+mod camera3d;
+mod exponential_map;
+mod formula;
 mod framebuffer;
 #[cfg(feature = "gpu")]
 mod gpu;
+mod interior;
+mod iteration;
+mod lyapunov;
+mod mandelbulb;
 mod math;
+mod projection;
+mod raw_data;
+mod relief;
+mod scene;
 mod text;
+mod trap;
 
 use cfg_if::cfg_if;
 
+#[cfg(feature = "image")]
+pub use animation::save_png_sequence;
+pub use animation::{write_y4m, Animation, Easing, Keyframe};
+pub use average::OrbitAverage;
+pub use bailout::{Bailout, BailoutNorm};
+pub use buddhabrot::{Buddhabrot, BuddhabrotType};
 pub use camera::Camera;
-pub use framebuffer::{Color, Draw, Fill, FrameBuffer};
-pub use math::{ColorType, Fractal, FractalType};
+pub use camera3d::Camera3D;
+pub use exponential_map::ExponentialMap;
+pub use formula::Formula;
+pub use framebuffer::{BlendMode, Color, Draw, Fill, FrameBuffer, HdrFrameBuffer, LinearColor};
+pub use interior::{Interior, InteriorColorType};
+pub use iteration::IterationFormula;
+pub use lyapunov::LyapunovSequence;
+pub use mandelbulb::Mandelbulb;
+pub use math::{ColorType, EscapeTime, Fractal, FractalType};
+pub use num_complex::Complex;
+pub use projection::{Projection, ProjectionType};
+pub use raw_data::{DataChannel, RawData};
+pub use relief::{HeightField, Relief};
+pub use scene::Scene;
 pub use text::Label;
+pub use trap::OrbitTrap;
 
 #[cfg(all(feature = "multithread", feature = "gpu"))]
 compile_error!("feature \"multithread\" and feature \"gpu\" cannot be enabled at the same time");
@@ -46,3 +81,10 @@ pub fn float(n: u32) -> Float {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) fn screen_size(width: u32, height: u32) -> cgmath::Vector2<std::num::NonZeroU32> {
+    use std::num::NonZeroU32;
+
+    cgmath::Vector2::new(NonZeroU32::new(width).unwrap(), NonZeroU32::new(height).unwrap())
+}
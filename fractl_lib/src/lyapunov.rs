@@ -0,0 +1,209 @@
+use std::fmt::Display;
+
+use cgmath::Vector2;
+
+use crate::{float, Float};
+
+// https://en.wikipedia.org/wiki/Lyapunov_fractal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LyapunovSequence {
+    // Bit n is set if the n-th rate of the sequence is B
+    bits: u64,
+    len: u8,
+}
+
+impl LyapunovSequence {
+    pub const MAX_LEN: usize = u64::BITS as usize;
+    pub const AB: Self = Self { bits: 0b10, len: 2 };
+
+    // Centers the default view on the classic [2, 4] x [2, 4] region of rates
+    const RATE_OFFSET: Float = 3.0;
+    const START_X: Float = 0.5;
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(sequence: &str) -> Result<Self, &'static str> {
+        if sequence.is_empty() {
+            Err("sequence cannot be empty string")
+        } else if sequence.len() > Self::MAX_LEN {
+            Err("sequence cannot be longer than 64 characters")
+        } else {
+            let mut bits = 0;
+
+            for (n, char) in sequence.chars().enumerate() {
+                match char.to_ascii_uppercase() {
+                    'A' => {}
+                    'B' => bits |= 1 << n,
+                    _ => return Err("sequence can only contain the characters A and B"),
+                }
+            }
+
+            Ok(Self {
+                bits,
+                #[allow(clippy::cast_possible_truncation)]
+                len: sequence.len() as u8,
+            })
+        }
+    }
+
+    #[must_use]
+    pub const fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> u8 {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Sequences in order of length and then bits, skipping the ones made of a single rate since those only show the
+    // logistic map along one axis
+    #[must_use]
+    pub fn next(&self) -> Self {
+        let mut sequence = *self;
+
+        loop {
+            if sequence.bits == Self::all_b(sequence.len) {
+                if usize::from(sequence.len) == Self::MAX_LEN {
+                    return *self;
+                }
+
+                sequence = Self {
+                    bits: 0,
+                    len: sequence.len + 1,
+                };
+            } else {
+                sequence.bits += 1;
+            }
+
+            if sequence.has_both_rates() {
+                return sequence;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn prev(&self) -> Self {
+        let mut sequence = *self;
+
+        loop {
+            if sequence.bits == 0 {
+                if sequence.len <= 2 {
+                    return *self;
+                }
+
+                sequence = Self {
+                    bits: Self::all_b(sequence.len - 1),
+                    len: sequence.len - 1,
+                };
+            } else {
+                sequence.bits -= 1;
+            }
+
+            if sequence.has_both_rates() {
+                return sequence;
+            }
+        }
+    }
+
+    const fn all_b(len: u8) -> u64 {
+        u64::MAX >> (u64::BITS - len as u32)
+    }
+
+    const fn has_both_rates(&self) -> bool {
+        self.bits != 0 && self.bits != Self::all_b(self.len)
+    }
+
+    fn rate(&self, n: u32, rates: Vector2<Float>) -> Float {
+        if self.bits & (1 << (n % u32::from(self.len))) == 0 {
+            rates.x
+        } else {
+            rates.y
+        }
+    }
+
+    // Iterates the logistic map x = r * x * (1 - x), switching the rate r between a and b by the sequence
+    pub(crate) fn exponent(&self, world_pos: Vector2<Float>, max_iterations: u32) -> Float {
+        let rates = world_pos.map(|x| x + Self::RATE_OFFSET);
+        let warmup_iterations = max_iterations / 4;
+
+        let mut x = Self::START_X;
+        for n in 0..warmup_iterations {
+            x = self.rate(n, rates) * x * (1.0 - x);
+        }
+
+        let mut sum = 0.0;
+        for n in warmup_iterations..warmup_iterations + max_iterations {
+            let rate = self.rate(n, rates);
+
+            sum += (rate * (1.0 - 2.0 * x)).abs().ln();
+            x = rate * x * (1.0 - x);
+        }
+
+        let exponent = sum / float(max_iterations);
+        if exponent.is_nan() {
+            Float::INFINITY
+        } else {
+            exponent
+        }
+    }
+}
+
+impl Default for LyapunovSequence {
+    fn default() -> Self {
+        Self::AB
+    }
+}
+
+impl Display for LyapunovSequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for n in 0..u32::from(self.len) {
+            write!(f, "{}", if self.bits & (1 << n) == 0 { 'A' } else { 'B' })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let sequence = LyapunovSequence::new("aabAB").unwrap();
+
+        assert_eq!(sequence.len(), 5);
+        assert_eq!(sequence.bits(), 0b10100);
+        assert_eq!(sequence.to_string(), "AABAB");
+        assert_eq!(LyapunovSequence::new("AB"), Ok(LyapunovSequence::AB));
+        assert_eq!(LyapunovSequence::new(&"B".repeat(64)).map(|x| x.len()), Ok(64));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(LyapunovSequence::new("").is_err());
+        assert!(LyapunovSequence::new("ABC").is_err());
+        assert!(LyapunovSequence::new(&"A".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn next_prev() {
+        let ab = LyapunovSequence::AB;
+
+        assert_eq!(ab.next().to_string(), "BAA");
+        assert_eq!(ab.prev().to_string(), "BA");
+        assert_eq!(ab.prev().prev().to_string(), "BA");
+        assert_eq!(LyapunovSequence::new("ABB").unwrap().next().to_string(), "BAAA");
+
+        let mut sequence = ab;
+        for _ in 0..100 {
+            sequence = sequence.next();
+            assert_eq!(sequence.prev().next(), sequence);
+        }
+    }
+}
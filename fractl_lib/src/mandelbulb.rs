@@ -0,0 +1,439 @@
+use std::num::NonZeroU32;
+
+use cfg_if::cfg_if;
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::{
+    float,
+    framebuffer::{Color, LinearColor},
+    Camera3D, Fill, Float, FrameBuffer, HdrFrameBuffer,
+};
+
+// https://en.wikipedia.org/wiki/Mandelbulb, rendered by sphere tracing its distance estimate
+// https://iquilezles.org/articles/mandelbulb/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mandelbulb {
+    power: Float,
+    camera: Camera3D,
+    max_iterations: NonZeroU32,
+    color: Color,
+    background: Color,
+    // Direction the light comes from
+    light_direction: Vector3<Float>,
+    ambient: Float,
+    specular: Float,
+    // Penumbra size of the soft shadows, higher is sharper
+    shadow_sharpness: Float,
+    ambient_occlusion: Float,
+}
+
+impl Mandelbulb {
+    const BAILOUT: Float = 2.0;
+    // Every point of the set lies inside this sphere for powers up to 8 and a bit above
+    const BOUNDING_RADIUS: Float = 1.3;
+    const MAX_STEPS: u32 = 256;
+    // Hit tolerance relative to the distance travelled, roughly the size of a pixel
+    const HIT_TOLERANCE: Float = 0.0005;
+    const SHADOW_STEPS: u32 = 64;
+    // Start of the shadow rays in hit tolerances, so the surface does not shadow itself
+    const SHADOW_OFFSET: Float = 20.0;
+    const AMBIENT_OCCLUSION_SAMPLES: u32 = 5;
+    const AMBIENT_OCCLUSION_STEP: Float = 0.02;
+    const SHININESS: i32 = 32;
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(power: Float, camera: Camera3D, max_iterations: NonZeroU32) -> Result<Self, &'static str> {
+        let mut mandelbulb = Self {
+            camera,
+            max_iterations,
+            ..Self::default()
+        };
+
+        mandelbulb.set_power(power)?;
+
+        Ok(mandelbulb)
+    }
+
+    #[must_use]
+    pub fn power(&self) -> Float {
+        self.power
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_power(&mut self, power: Float) -> Result<(), &'static str> {
+        if power.is_finite() && power >= 2.0 {
+            self.power = power;
+            Ok(())
+        } else {
+            Err("power must be finite and at least 2.0")
+        }
+    }
+
+    #[must_use]
+    pub fn camera(&self) -> &Camera3D {
+        &self.camera
+    }
+
+    #[must_use]
+    pub fn camera_mut(&mut self) -> &mut Camera3D {
+        &mut self.camera
+    }
+
+    #[must_use]
+    pub fn max_iterations(&self) -> NonZeroU32 {
+        self.max_iterations
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: NonZeroU32) {
+        self.max_iterations = max_iterations;
+    }
+
+    #[must_use]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    #[must_use]
+    pub fn background(&self) -> Color {
+        self.background
+    }
+
+    pub fn set_background(&mut self, background: Color) {
+        self.background = background;
+    }
+
+    #[must_use]
+    pub fn light_direction(&self) -> Vector3<Float> {
+        self.light_direction
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_light_direction(&mut self, light_direction: Vector3<Float>) -> Result<(), &'static str> {
+        let light_direction = light_direction.normalize();
+
+        if light_direction.x.is_finite() && light_direction.y.is_finite() && light_direction.z.is_finite() {
+            self.light_direction = light_direction;
+            Ok(())
+        } else {
+            Err("light direction must be finite and not zero")
+        }
+    }
+
+    #[must_use]
+    pub fn ambient(&self) -> Float {
+        self.ambient
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_ambient(&mut self, ambient: Float) -> Result<(), &'static str> {
+        if ambient.is_finite() && (0.0..=1.0).contains(&ambient) {
+            self.ambient = ambient;
+            Ok(())
+        } else {
+            Err("ambient must be between 0 and 1.0 (inclusive)")
+        }
+    }
+
+    #[must_use]
+    pub fn specular(&self) -> Float {
+        self.specular
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_specular(&mut self, specular: Float) -> Result<(), &'static str> {
+        if specular.is_finite() && (0.0..=1.0).contains(&specular) {
+            self.specular = specular;
+            Ok(())
+        } else {
+            Err("specular must be between 0 and 1.0 (inclusive)")
+        }
+    }
+
+    #[must_use]
+    pub fn shadow_sharpness(&self) -> Float {
+        self.shadow_sharpness
+    }
+
+    // Zero turns the shadows off
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_shadow_sharpness(&mut self, shadow_sharpness: Float) -> Result<(), &'static str> {
+        if shadow_sharpness.is_finite() && shadow_sharpness >= 0.0 {
+            self.shadow_sharpness = shadow_sharpness;
+            Ok(())
+        } else {
+            Err("shadow sharpness must be finite and not negative")
+        }
+    }
+
+    #[must_use]
+    pub fn ambient_occlusion(&self) -> Float {
+        self.ambient_occlusion
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_ambient_occlusion(&mut self, ambient_occlusion: Float) -> Result<(), &'static str> {
+        if ambient_occlusion.is_finite() && ambient_occlusion >= 0.0 {
+            self.ambient_occlusion = ambient_occlusion;
+            Ok(())
+        } else {
+            Err("ambient occlusion must be finite and not negative")
+        }
+    }
+
+    // Lower bound of the distance to the set and the smallest |z|^2 of the orbit, iterating the triplex
+    // z = z^n + c in spherical coordinates
+    #[must_use]
+    pub fn distance(&self, pos: Vector3<Float>) -> (Float, Float) {
+        let mut z = pos;
+        let mut dr = 1.0;
+        let mut r = z.magnitude();
+        let mut trap = r * r;
+
+        for _ in 0..self.max_iterations.get() {
+            if r > Self::BAILOUT {
+                break;
+            }
+
+            let theta = (z.z / r).acos() * self.power;
+            let phi = z.y.atan2(z.x) * self.power;
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+
+            z = Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) * r.powf(self.power) + pos;
+            r = z.magnitude();
+            trap = trap.min(r * r);
+        }
+
+        // Not defined at the origin and for orbits that never escape, those points count as inside
+        let distance = 0.5 * r.ln() * r / dr;
+        (if distance.is_finite() { distance.max(0.0) } else { 0.0 }, trap)
+    }
+
+    // Distance along the ray to the first hit, skipping to the bounding sphere first
+    fn march(&self, origin: Vector3<Float>, direction: Vector3<Float>) -> Option<Float> {
+        // https://en.wikipedia.org/wiki/Line%E2%80%93sphere_intersection
+        let b = origin.dot(direction);
+        let discriminant = b * b - origin.magnitude2() + Self::BOUNDING_RADIUS.powi(2);
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let mut t = (-b - discriminant.sqrt()).max(0.0);
+        let t_max = -b + discriminant.sqrt();
+
+        for _ in 0..Self::MAX_STEPS {
+            let (distance, _) = self.distance(origin + direction * t);
+
+            if distance < Self::HIT_TOLERANCE * t.max(1.0) {
+                return Some(t);
+            }
+
+            t += distance;
+            if t > t_max {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    // Central differences of the distance estimate
+    fn normal(&self, pos: Vector3<Float>, epsilon: Float) -> Vector3<Float> {
+        let gradient =
+            |axis: Vector3<Float>| self.distance(pos + axis * epsilon).0 - self.distance(pos - axis * epsilon).0;
+
+        let normal = Vector3::new(
+            gradient(Vector3::unit_x()),
+            gradient(Vector3::unit_y()),
+            gradient(Vector3::unit_z()),
+        );
+
+        if normal.magnitude2() > 0.0 {
+            normal.normalize()
+        } else {
+            -self.camera.direction
+        }
+    }
+
+    // https://iquilezles.org/articles/rmshadows/
+    fn soft_shadow(&self, pos: Vector3<Float>, epsilon: Float) -> Float {
+        if self.shadow_sharpness == 0.0 {
+            return 1.0;
+        }
+
+        let mut shadow: Float = 1.0;
+        let mut t = epsilon * Self::SHADOW_OFFSET;
+
+        for _ in 0..Self::SHADOW_STEPS {
+            let point = pos + self.light_direction * t;
+            if point.magnitude() > Self::BOUNDING_RADIUS {
+                break;
+            }
+
+            let (distance, _) = self.distance(point);
+            if distance < epsilon {
+                return 0.0;
+            }
+
+            shadow = shadow.min(self.shadow_sharpness * distance / t);
+            t += distance.max(epsilon);
+        }
+
+        shadow.clamp(0.0, 1.0)
+    }
+
+    // Fraction of samples along the normal that are closer to the set than to the surface, nearer ones weighted more
+    // https://iquilezles.org/articles/nvscene2008/rwwtt.pdf
+    fn occlusion(&self, pos: Vector3<Float>, normal: Vector3<Float>) -> Float {
+        let (mut occlusion, mut total_weight) = (0.0, 0.0);
+        let mut weight = 1.0;
+
+        for n in 1..=Self::AMBIENT_OCCLUSION_SAMPLES {
+            let step = Self::AMBIENT_OCCLUSION_STEP * float(n);
+            let (distance, _) = self.distance(pos + normal * step);
+
+            occlusion += weight * (1.0 - distance / step).max(0.0);
+            total_weight += weight;
+            weight *= 0.5;
+        }
+
+        (1.0 - self.ambient_occlusion * occlusion / total_weight).clamp(0.0, 1.0)
+    }
+
+    fn pixel_color(&self, screen_pos: Vector2<u32>, screen_size: Vector2<NonZeroU32>) -> LinearColor {
+        let direction = self.camera.ray_direction(&screen_pos, &screen_size);
+
+        let Some(t) = self.march(self.camera.position, direction) else {
+            return LinearColor::from(self.background);
+        };
+
+        let pos = self.camera.position + direction * t;
+        let epsilon = Self::HIT_TOLERANCE * t.max(1.0);
+        let normal = self.normal(pos, epsilon);
+        let surface_pos = pos + normal * epsilon * 2.0;
+
+        let (_, trap) = self.distance(pos);
+        let diffuse = normal.dot(self.light_direction).max(0.0);
+        let shadow = if diffuse > 0.0 {
+            self.soft_shadow(surface_pos, epsilon)
+        } else {
+            0.0
+        };
+        let occlusion = self.occlusion(surface_pos, normal);
+        let halfway = (self.light_direction - direction).normalize();
+        let specular = self.specular * normal.dot(halfway).max(0.0).powi(Self::SHININESS) * shadow;
+
+        // Darkens the crevices of the orbit trap to bring out the structure of the surface
+        let albedo = trap.sqrt().clamp(0.0, 1.0).mul_add(0.5, 0.5);
+        let lightness = albedo * (self.ambient * occlusion + (1.0 - self.ambient) * diffuse * shadow);
+
+        // Shaded in gamma space like `Relief`, highlights can go above 1.0
+        #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+        let shade_channel = |channel: u8| (Float::from(channel) / 255.0 * lightness + specular).max(0.0) as f32;
+
+        LinearColor::from_srgb(
+            shade_channel(self.color.red()),
+            shade_channel(self.color.green()),
+            shade_channel(self.color.blue()),
+        )
+    }
+
+    // Full precision render
+    pub fn fill_hdr(&self, buffer: &mut HdrFrameBuffer) {
+        let screen_size = *buffer.size();
+        let index_to_color = |index: u32| {
+            let screen_pos = Vector2::new(index % screen_size.x.get(), index / screen_size.x.get());
+
+            self.pixel_color(screen_pos, screen_size)
+        };
+
+        buffer.data = {
+            cfg_if! {
+                if #[cfg(feature = "multithread")] {
+                    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+                    (0..screen_size.x.get() * screen_size.y.get())
+                        .into_par_iter()
+                        .map(index_to_color)
+                        .collect::<Vec<_>>()
+                } else {
+                    (0..screen_size.x.get() * screen_size.y.get())
+                        .map(index_to_color)
+                        .collect::<Vec<_>>()
+                }
+            }
+        };
+    }
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Self {
+            power: 8.0,
+            camera: Camera3D::default(),
+            max_iterations: NonZeroU32::new(12).unwrap(),
+            color: Color::new(230, 180, 110),
+            background: Color::new(20, 20, 30),
+            light_direction: Vector3::new(-0.4, -0.8, 0.6).normalize(),
+            ambient: 0.3,
+            specular: 0.3,
+            shadow_sharpness: 8.0,
+            ambient_occlusion: 1.0,
+        }
+    }
+}
+
+impl Fill for Mandelbulb {
+    fn fill(&self, buffer: &mut FrameBuffer) {
+        let mut hdr_buffer = HdrFrameBuffer::new(*buffer.size());
+        self.fill_hdr(&mut hdr_buffer);
+
+        *buffer = hdr_buffer.to_framebuffer(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        assert!(Mandelbulb::new(8.0, Camera3D::default(), NonZeroU32::new(12).unwrap()).is_ok());
+        assert!(Mandelbulb::new(1.0, Camera3D::default(), NonZeroU32::new(12).unwrap()).is_err());
+        assert!(Mandelbulb::new(Float::NAN, Camera3D::default(), NonZeroU32::new(12).unwrap()).is_err());
+    }
+
+    #[test]
+    fn distance() {
+        let mandelbulb = Mandelbulb::default();
+
+        assert!(mandelbulb.distance(Vector3::new(0.0, 0.0, 0.0)).0 < Float::EPSILON);
+
+        for pos in [
+            Vector3::new(1.5, 0.0, 0.0),
+            Vector3::new(0.0, -2.0, 0.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ] {
+            let (distance, _) = mandelbulb.distance(pos);
+
+            assert!(pos.magnitude() > Mandelbulb::BOUNDING_RADIUS);
+            // A lower bound of the distance, the set lies inside the bounding sphere
+            assert!(distance > 0.0 && distance <= pos.magnitude(), "{pos:?}: {distance}");
+        }
+    }
+
+    #[test]
+    fn march() {
+        let mandelbulb = Mandelbulb::default();
+        let origin = Vector3::new(0.0, -3.0, 0.0);
+
+        assert_eq!(mandelbulb.march(origin, Vector3::new(0.0, -1.0, 0.0)), None);
+        assert_eq!(mandelbulb.march(origin, Vector3::new(0.0, 0.0, 1.0)), None);
+
+        let t = mandelbulb.march(origin, Vector3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!(t > 3.0 - Mandelbulb::BOUNDING_RADIUS && t < 3.0, "{t}");
+    }
+}
@@ -1,27 +1,148 @@
-use std::{fmt::Display, num::NonZeroU32};
+use std::{fmt::Display, num::NonZeroU32, sync::Arc};
 
 use cfg_if::cfg_if;
 use cgmath::Vector2;
+use num_complex::Complex;
 
-use crate::{float, framebuffer::Color, Camera, Fill, Float};
+use crate::{
+    average::OrbitAverageTracker,
+    float,
+    framebuffer::{Color, HdrFrameBuffer, LinearColor},
+    iteration::{iterate, periodicity},
+    trap::OrbitTrapTracker,
+    Bailout, Camera, Fill, Float, Formula, Interior, InteriorColorType, IterationFormula, LyapunovSequence,
+    OrbitAverage, OrbitTrap, Relief,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EscapeTime {
+    pub iterations: u32,
+    /// Continuous iteration count, equal to `iterations` for points inside the set
+    pub smooth_iterations: Float,
+    pub z: Vector2<Float>,
+    /// Estimated distance (in world units) to the set, `0.0` for points inside it
+    pub distance: Float,
+    /// Minimum distance of the orbit from the orbit trap, infinite without one
+    pub trap_distance: Float,
+    pub trap_color: Option<Color>,
+    /// Orbit average between 0 and 1.0, interpolated with the fractional part of `smooth_iterations`
+    pub orbit_average: Option<Float>,
+    /// Only computed for points inside the set when the interior coloring needs it
+    pub interior: Option<Interior>,
+    /// Set if the orbit stopped by converging to a fixed point instead of escaping
+    pub converged: bool,
+    /// Negative for stable (ordered) regions, positive for chaotic ones, only computed by Lyapunov fractals
+    pub lyapunov_exponent: Option<Float>,
+}
+
+impl EscapeTime {
+    pub(crate) fn new(
+        iterations: u32,
+        max_iterations: u32,
+        z: Vector2<Float>,
+        dz: Vector2<Float>,
+        degree: Float,
+        bailout: Bailout,
+    ) -> Self {
+        if iterations < max_iterations {
+            let z_abs = (z.x.powi(2) + z.y.powi(2)).sqrt();
+            let dz_abs = (dz.x.powi(2) + dz.y.powi(2)).sqrt();
+
+            // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Continuous_(smooth)_coloring
+            let smooth_iterations =
+                float(iterations) - (bailout.norm().norm(z).ln() / bailout.radius().ln()).ln() / degree.ln();
+
+            // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Distance_estimates
+            let distance = (z_abs * z_abs.ln() / dz_abs).max(0.0);
+
+            Self {
+                iterations,
+                smooth_iterations: if smooth_iterations.is_finite() {
+                    smooth_iterations
+                } else {
+                    float(iterations)
+                },
+                z,
+                distance,
+                trap_distance: Float::INFINITY,
+                trap_color: None,
+                orbit_average: None,
+                interior: None,
+                converged: false,
+                lyapunov_exponent: None,
+            }
+        } else {
+            Self {
+                iterations,
+                smooth_iterations: float(iterations),
+                z,
+                distance: 0.0,
+                trap_distance: Float::INFINITY,
+                trap_color: None,
+                orbit_average: None,
+                interior: None,
+                converged: false,
+                lyapunov_exponent: None,
+            }
+        }
+    }
+
+    pub(crate) fn with_orbit_trap(mut self, orbit_trap_tracker: Option<OrbitTrapTracker>) -> Self {
+        if let Some(orbit_trap_tracker) = orbit_trap_tracker {
+            self.trap_distance = orbit_trap_tracker.distance;
+            self.trap_color = orbit_trap_tracker.color;
+        }
+
+        self
+    }
+
+    pub(crate) fn with_orbit_average(mut self, orbit_average_tracker: Option<OrbitAverageTracker>) -> Self {
+        if let Some(orbit_average_tracker) = orbit_average_tracker {
+            let smooth_fraction = self.smooth_iterations - (float(self.iterations) - 1.0);
+            self.orbit_average = orbit_average_tracker.average(smooth_fraction.clamp(0.0, 1.0));
+        }
+
+        self
+    }
+}
 
 #[non_exhaustive]
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum FractalType {
     #[default]
     Mandelbrot,
     Multibrot(Float),
+    Lyapunov(LyapunovSequence),
+    Custom(Formula),
+    // https://en.wikipedia.org/wiki/Phoenix_fractal
+    Phoenix(Complex<Float>),
+    // https://en.wikipedia.org/wiki/Newton_fractal#Nova_fractal
+    Nova(Complex<Float>),
+    // https://paulbourke.net/fractals/magnet/
+    MagnetI,
+    MagnetII,
 }
 
 impl FractalType {
-    const NUM_OF_VARIANTS: u8 = 2;
+    const NUM_OF_VARIANTS: u8 = 8;
     const DEFAULT_MULTIBROT_ARGUEMENT: Float = 4.0;
+    const DEFAULT_PHOENIX_ARGUEMENT: Complex<Float> = Complex::new(-0.5, 0.0);
+    const DEFAULT_NOVA_ARGUEMENT: Complex<Float> = Complex::new(1.0, 0.0);
+    const CONVERGENCE_TOLERANCE: Float = 1e-6;
+    const MAGNET_BAILOUT: Float = 100.0;
 
     #[must_use]
     pub const fn id(&self) -> u8 {
         match self {
             Self::Mandelbrot => 0,
             Self::Multibrot(_) => 1,
+            Self::Lyapunov(_) => 2,
+            Self::Custom(_) => 3,
+            Self::Phoenix(_) => 4,
+            Self::Nova(_) => 5,
+            Self::MagnetI => 6,
+            Self::MagnetII => 7,
         }
     }
 
@@ -30,6 +151,12 @@ impl FractalType {
         match id % Self::NUM_OF_VARIANTS {
             0 => Self::Mandelbrot,
             1 => Self::Multibrot(Self::DEFAULT_MULTIBROT_ARGUEMENT),
+            2 => Self::Lyapunov(LyapunovSequence::AB),
+            3 => Self::Custom(Formula::BURNING_SHIP),
+            4 => Self::Phoenix(Self::DEFAULT_PHOENIX_ARGUEMENT),
+            5 => Self::Nova(Self::DEFAULT_NOVA_ARGUEMENT),
+            6 => Self::MagnetI,
+            7 => Self::MagnetII,
             _ => unreachable!(),
         }
     }
@@ -44,11 +171,26 @@ impl FractalType {
         Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
     }
 
+    // Changes the Multibrot exponent or the real part of the Phoenix and Nova parameters
     pub fn change_multi_parametr(&mut self, by: Float) {
-        if let Self::Multibrot(exponent) = self {
-            let new_exponent = *exponent + by;
-            if new_exponent.is_finite() {
-                *self = FractalType::Multibrot(new_exponent);
+        match self {
+            Self::Multibrot(exponent) => {
+                let new_exponent = *exponent + by;
+                if new_exponent.is_finite() {
+                    *self = FractalType::Multibrot(new_exponent);
+                }
+            }
+            Self::Phoenix(parametr) | Self::Nova(parametr) if (parametr.re + by).is_finite() => parametr.re += by,
+            Self::Lyapunov(sequence) if by > 0.0 => *sequence = sequence.next(),
+            Self::Lyapunov(sequence) if by < 0.0 => *sequence = sequence.prev(),
+            _ => {}
+        }
+    }
+
+    pub fn change_multi_parametr_imaginary(&mut self, by: Float) {
+        if let Self::Phoenix(parametr) | Self::Nova(parametr) = self {
+            if (parametr.im + by).is_finite() {
+                parametr.im += by;
             }
         }
     }
@@ -60,11 +202,130 @@ impl FractalType {
             _ => None,
         }
     }
+}
 
-    #[must_use]
-    pub fn escape_time(&self, world_pos: Vector2<Float>, max_iterations: NonZeroU32) -> u32 {
+impl FractalType {
+    // (df/dz, d^2f/dz^2) for formulas where df/dc is always 1
+    fn step_derivatives(&self, z: Complex<Float>) -> Option<(Complex<Float>, Complex<Float>)> {
+        match self {
+            Self::Mandelbrot => Some((2.0 * z, Complex::new(2.0, 0.0))),
+            Self::Multibrot(exponent) => Some((
+                *exponent * z.powf(exponent - 1.0),
+                *exponent * (exponent - 1.0) * z.powf(exponent - 2.0),
+            )),
+            Self::Lyapunov(_) | Self::Custom(_) | Self::Phoenix(_) | Self::Nova(_) | Self::MagnetI | Self::MagnetII => {
+                None
+            }
+        }
+    }
+}
+
+impl IterationFormula for FractalType {
+    fn name(&self) -> &str {
+        match self {
+            Self::Mandelbrot => "Mandelbrot",
+            Self::Multibrot(_) => "Multibrot",
+            Self::Lyapunov(_) => "Lyapunov",
+            Self::Custom(_) => "Custom",
+            Self::Phoenix(_) => "Phoenix",
+            Self::Nova(_) => "Nova",
+            Self::MagnetI => "Magnet I",
+            Self::MagnetII => "Magnet II",
+        }
+    }
+
+    fn parameters(&self) -> Vec<(&str, Float)> {
+        match self {
+            Self::Multibrot(exponent) => vec![("exponent", *exponent)],
+            Self::Phoenix(p) => vec![("re(p)", p.re), ("im(p)", p.im)],
+            Self::Nova(relaxation) => vec![("re(R)", relaxation.re), ("im(R)", relaxation.im)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn init(&self, c: Complex<Float>) -> Complex<Float> {
+        match self {
+            Self::Mandelbrot | Self::Phoenix(_) | Self::MagnetI | Self::MagnetII => Complex::new(0.0, 0.0),
+            Self::Multibrot(_) | Self::Lyapunov(_) | Self::Custom(_) => c,
+            // Critical point of z^3 - 1
+            Self::Nova(_) => Complex::new(1.0, 0.0),
+        }
+    }
+
+    fn init_derivative(&self, _c: Complex<Float>) -> Complex<Float> {
+        match self {
+            Self::Mandelbrot | Self::Phoenix(_) | Self::MagnetI | Self::MagnetII | Self::Nova(_) => {
+                Complex::new(0.0, 0.0)
+            }
+            Self::Multibrot(_) | Self::Lyapunov(_) | Self::Custom(_) => Complex::new(1.0, 0.0),
+        }
+    }
+
+    fn step(&self, z: Complex<Float>, previous_z: Complex<Float>, c: Complex<Float>) -> Complex<Float> {
+        match self {
+            Self::Mandelbrot => z * z + c,
+            Self::Multibrot(exponent) => z.powf(*exponent) + c,
+            // Lyapunov fractals iterate the logistic map instead of the complex plane, so their orbits never escape
+            Self::Lyapunov(_) => z,
+            Self::Custom(formula) => formula.evaluate(z, Complex::new(0.0, 0.0), c, Complex::new(0.0, 0.0)).0,
+            Self::Phoenix(p) => z * z + c + p * previous_z,
+            // Relaxed Newton's method for z^3 - 1 with c added
+            Self::Nova(relaxation) => z - relaxation * (z.powi(3) - 1.0) / (3.0 * z.powi(2)) + c,
+            Self::MagnetI => ((z * z + c - 1.0) / (2.0 * z + c - 2.0)).powi(2),
+            Self::MagnetII => {
+                let (c_1, c_2) = (c - 1.0, c - 2.0);
+
+                ((z.powi(3) + 3.0 * c_1 * z + c_1 * c_2) / (3.0 * z * z + 3.0 * c_2 * z + c_1 * c_2 + 1.0)).powi(2)
+            }
+        }
+    }
+
+    fn bailout(&self) -> Option<Float> {
+        match self {
+            Self::Mandelbrot | Self::Phoenix(_) => Some(2.0),
+            Self::Multibrot(exponent) => Some(exponent.abs()),
+            // Lyapunov orbits do not live in the complex plane and Nova orbits are stopped by converging instead
+            Self::Lyapunov(_) | Self::Nova(_) => None,
+            Self::Custom(_) => Some(Formula::BAILOUT),
+            Self::MagnetI | Self::MagnetII => Some(Self::MAGNET_BAILOUT),
+        }
+    }
+
+    fn degree(&self) -> Float {
+        self.multi_parametr().unwrap_or(2.0)
+    }
+
+    fn convergence_tolerance(&self) -> Option<Float> {
+        match self {
+            Self::Nova(_) | Self::MagnetI | Self::MagnetII => Some(Self::CONVERGENCE_TOLERANCE),
+            _ => None,
+        }
+    }
+
+    fn derivatives(&self, z: Complex<Float>, c: Complex<Float>) -> Option<(Complex<Float>, Complex<Float>)> {
+        let one = Complex::new(1.0, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+
+        match self {
+            Self::Mandelbrot | Self::Multibrot(_) => self.step_derivatives(z).map(|(f_z, _)| (f_z, one)),
+            Self::Lyapunov(_) | Self::Phoenix(_) | Self::Nova(_) | Self::MagnetI | Self::MagnetII => None,
+            Self::Custom(formula) => Some((formula.evaluate(z, one, c, zero).1, formula.evaluate(z, zero, c, one).1)),
+        }
+    }
+
+    fn escape_time(
+        &self,
+        world_pos: Vector2<Float>,
+        max_iterations: NonZeroU32,
+        bailout: Bailout,
+        orbit_trap: Option<&OrbitTrap>,
+        orbit_average: Option<OrbitAverage>,
+    ) -> EscapeTime {
         let mut n = 0;
         let max_iterations = max_iterations.get();
+        let mut orbit_trap_tracker = orbit_trap.map(OrbitTrapTracker::new);
+        let mut orbit_average_tracker =
+            orbit_average.map(|orbit_average| OrbitAverageTracker::new(orbit_average, world_pos, self.degree()));
 
         match self {
             Self::Mandelbrot => {
@@ -75,42 +336,216 @@ impl FractalType {
                     q * (q + (world_pos.x - 0.25)) <= 0.25 * world_pos.y.powi(2)
                 };
 
-                if is_in_main_bulb {
-                    max_iterations
+                // Orbits of the main bulb stay within |z| <= 2, but may leave smaller bailout regions
+                if is_in_main_bulb
+                    && bailout.radius() >= 2.0
+                    && orbit_trap_tracker.is_none()
+                    && orbit_average_tracker.is_none()
+                {
+                    EscapeTime::new(
+                        max_iterations,
+                        max_iterations,
+                        Vector2::new(0.0, 0.0),
+                        Vector2::new(0.0, 0.0),
+                        2.0,
+                        bailout,
+                    )
                 } else {
                     let (mut x2, mut y2, mut x, mut y) = (0.0, 0.0, 0.0, 0.0);
+                    let (mut dx, mut dy) = (0.0, 0.0);
+
+                    while bailout.contains(Vector2::new(x, y)) && (n < max_iterations) {
+                        // dz = 2 * z * dz + 1
+                        let dx_tmp = 2.0 * (x * dx - y * dy) + 1.0;
+                        dy = 2.0 * (x * dy + y * dx);
+                        dx = dx_tmp;
 
-                    while (x2 + y2 <= 4.0) && (n < max_iterations) {
                         y = 2.0 * x * y + world_pos.y;
                         x = x2 - y2 + world_pos.x;
 
                         x2 = x.powi(2);
                         y2 = y.powi(2);
 
+                        if let Some(orbit_trap_tracker) = &mut orbit_trap_tracker {
+                            orbit_trap_tracker.update(Vector2::new(x, y));
+                        }
+                        if let Some(orbit_average_tracker) = &mut orbit_average_tracker {
+                            orbit_average_tracker.update(Vector2::new(x, y));
+                        }
+
                         n += 1;
                     }
 
-                    n
+                    EscapeTime::new(
+                        n,
+                        max_iterations,
+                        Vector2::new(x, y),
+                        Vector2::new(dx, dy),
+                        2.0,
+                        bailout,
+                    )
+                    .with_orbit_trap(orbit_trap_tracker)
+                    .with_orbit_average(orbit_average_tracker)
                 }
             }
             Self::Multibrot(exponent) => {
                 // https://en.wikipedia.org/wiki/Multibrot_set#Rendering_images
 
                 let (mut x, mut y) = (world_pos.x, world_pos.y);
+                let (mut dx, mut dy) = (1.0, 0.0);
+
+                while bailout.contains(Vector2::new(x, y)) && (n < max_iterations) {
+                    let x_y_squared = x.powi(2) + y.powi(2);
+                    let atan = y.atan2(x);
+
+                    // dz = exponent * z^(exponent - 1) * dz + 1
+                    let dz_mult = exponent * x_y_squared.powf((exponent - 1.0) / 2.0);
+                    let dz_atan = (exponent - 1.0) * atan;
+                    let (dz_mult_x, dz_mult_y) = (dz_mult * dz_atan.cos(), dz_mult * dz_atan.sin());
+                    let dx_tmp = dz_mult_x * dx - dz_mult_y * dy + 1.0;
+                    dy = dz_mult_x * dy + dz_mult_y * dx;
+                    dx = dx_tmp;
 
-                while ((x.powi(2) + y.powi(2)) <= exponent.powi(2)) && (n < max_iterations) {
-                    let x_y_squared_exp = (x.powi(2) + y.powi(2)).powf(exponent / 2.0);
-                    let exponent_atan = exponent * y.atan2(x);
+                    let x_y_squared_exp = x_y_squared.powf(exponent / 2.0);
+                    let exponent_atan = exponent * atan;
 
                     let x_tmp = x_y_squared_exp * (exponent_atan).cos() + world_pos.x;
                     y = x_y_squared_exp * (exponent_atan).sin() + world_pos.y;
                     x = x_tmp;
 
+                    if let Some(orbit_trap_tracker) = &mut orbit_trap_tracker {
+                        orbit_trap_tracker.update(Vector2::new(x, y));
+                    }
+                    if let Some(orbit_average_tracker) = &mut orbit_average_tracker {
+                        orbit_average_tracker.update(Vector2::new(x, y));
+                    }
+
+                    n += 1;
+                }
+
+                EscapeTime::new(
+                    n,
+                    max_iterations,
+                    Vector2::new(x, y),
+                    Vector2::new(dx, dy),
+                    *exponent,
+                    bailout,
+                )
+                .with_orbit_trap(orbit_trap_tracker)
+                .with_orbit_average(orbit_average_tracker)
+            }
+            Self::Lyapunov(sequence) => EscapeTime {
+                iterations: 0,
+                smooth_iterations: 0.0,
+                z: Vector2::new(0.0, 0.0),
+                distance: 0.0,
+                trap_distance: Float::INFINITY,
+                trap_color: None,
+                orbit_average: None,
+                interior: None,
+                converged: false,
+                lyapunov_exponent: Some(sequence.exponent(world_pos, max_iterations)),
+            },
+            Self::Custom(formula) => {
+                let c = Complex::new(world_pos.x, world_pos.y);
+                let (mut z, mut dz) = (c, Complex::new(1.0, 0.0));
+
+                while bailout.contains(Vector2::new(z.re, z.im)) && (n < max_iterations) {
+                    (z, dz) = formula.evaluate(z, dz, c, Complex::new(1.0, 0.0));
+
+                    if let Some(orbit_trap_tracker) = &mut orbit_trap_tracker {
+                        orbit_trap_tracker.update(Vector2::new(z.re, z.im));
+                    }
+                    if let Some(orbit_average_tracker) = &mut orbit_average_tracker {
+                        orbit_average_tracker.update(Vector2::new(z.re, z.im));
+                    }
+
                     n += 1;
                 }
 
-                n
+                // The degree of a custom formula is unknown, so smooth iterations assume a quadratic one
+                EscapeTime::new(
+                    n,
+                    max_iterations,
+                    Vector2::new(z.re, z.im),
+                    Vector2::new(dz.re, dz.im),
+                    2.0,
+                    bailout,
+                )
+                .with_orbit_trap(orbit_trap_tracker)
+                .with_orbit_average(orbit_average_tracker)
+            }
+            Self::Phoenix(p) => {
+                let c = Complex::new(world_pos.x, world_pos.y);
+                let (mut z, mut previous_z) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+                let (mut dz, mut dz_previous) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+
+                while bailout.contains(Vector2::new(z.re, z.im)) && (n < max_iterations) {
+                    // dz = 2 * z * dz + 1 + p * dz_previous
+                    (dz, dz_previous) = (2.0 * z * dz + 1.0 + p * dz_previous, dz);
+                    (z, previous_z) = (self.step(z, previous_z, c), z);
+
+                    if let Some(orbit_trap_tracker) = &mut orbit_trap_tracker {
+                        orbit_trap_tracker.update(Vector2::new(z.re, z.im));
+                    }
+                    if let Some(orbit_average_tracker) = &mut orbit_average_tracker {
+                        orbit_average_tracker.update(Vector2::new(z.re, z.im));
+                    }
+
+                    n += 1;
+                }
+
+                EscapeTime::new(
+                    n,
+                    max_iterations,
+                    Vector2::new(z.re, z.im),
+                    Vector2::new(dz.re, dz.im),
+                    2.0,
+                    bailout,
+                )
+                .with_orbit_trap(orbit_trap_tracker)
+                .with_orbit_average(orbit_average_tracker)
+            }
+            Self::Nova(_) | Self::MagnetI | Self::MagnetII => {
+                iterate(self, world_pos, max_iterations, bailout, orbit_trap, orbit_average)
+            }
+        }
+    }
+
+    fn interior(&self, world_pos: Vector2<Float>, max_iterations: NonZeroU32) -> Interior {
+        let c = Complex::new(world_pos.x, world_pos.y);
+        let (z, period) = periodicity(self, c, max_iterations);
+
+        // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Interior_distance_estimation
+        let distance = period.and_then(|period| {
+            let (mut z_cycle, mut dz, mut dc, mut dzz, mut dcz) = (
+                z,
+                Complex::new(1.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0),
+            );
+
+            for _ in 0..period.get() {
+                let (f_z, f_zz) = self.step_derivatives(z_cycle)?;
+
+                dcz = f_zz * dc * dz + f_z * dcz;
+                dzz = f_zz * dz * dz + f_z * dzz;
+                dc = f_z * dc + 1.0;
+                dz = f_z * dz;
+                // Only formulas without history have step derivatives
+                z_cycle = self.step(z_cycle, Complex::new(0.0, 0.0), c);
             }
+
+            let distance = (1.0 - dz.norm_sqr()) / (dcz + dzz * dc / (1.0 - dz)).norm();
+
+            (distance.is_finite() && distance > 0.0).then_some(distance)
+        });
+
+        Interior {
+            z: Vector2::new(z.re, z.im),
+            period,
+            distance,
         }
     }
 }
@@ -120,6 +555,12 @@ impl Display for FractalType {
         match self {
             FractalType::Mandelbrot => write!(f, "Mandelbrot"),
             FractalType::Multibrot(exponent) => write!(f, "Multibrot ({exponent:?})"),
+            FractalType::Lyapunov(sequence) => write!(f, "Lyapunov ({sequence})"),
+            FractalType::Custom(formula) => write!(f, "Custom ({formula})"),
+            FractalType::Phoenix(p) => write!(f, "Phoenix ({p})"),
+            FractalType::Nova(relaxation) => write!(f, "Nova ({relaxation})"),
+            FractalType::MagnetI => write!(f, "Magnet I"),
+            FractalType::MagnetII => write!(f, "Magnet II"),
         }?;
 
         Ok(())
@@ -133,10 +574,18 @@ pub enum ColorType {
     Histogram,
     LCH,
     OLC,
+    Distance,
+    OrbitTrap,
+    OrbitTrapImage,
+    StripeAverage,
+    TriangleInequalityAverage,
+    CurvatureAverage,
+    Lyapunov,
+    Convergence,
 }
 
 impl ColorType {
-    const NUM_OF_VARIANTS: u8 = 3;
+    const NUM_OF_VARIANTS: u8 = 11;
 
     #[must_use]
     pub const fn id(&self) -> u8 {
@@ -144,6 +593,14 @@ impl ColorType {
             Self::Histogram => 0,
             Self::LCH => 1,
             Self::OLC => 2,
+            Self::Distance => 3,
+            Self::OrbitTrap => 4,
+            Self::OrbitTrapImage => 5,
+            Self::StripeAverage => 6,
+            Self::TriangleInequalityAverage => 7,
+            Self::CurvatureAverage => 8,
+            Self::Lyapunov => 9,
+            Self::Convergence => 10,
         }
     }
 
@@ -153,6 +610,14 @@ impl ColorType {
             0 => Self::Histogram,
             1 => Self::LCH,
             2 => Self::OLC,
+            3 => Self::Distance,
+            4 => Self::OrbitTrap,
+            5 => Self::OrbitTrapImage,
+            6 => Self::StripeAverage,
+            7 => Self::TriangleInequalityAverage,
+            8 => Self::CurvatureAverage,
+            9 => Self::Lyapunov,
+            10 => Self::Convergence,
             _ => unreachable!(),
         }
     }
@@ -168,15 +633,45 @@ impl ColorType {
     }
 
     #[must_use]
-    pub fn escape_time_color(&self, escape_time: u32, max_iterations: NonZeroU32) -> Color {
+    pub const fn orbit_average(&self) -> Option<OrbitAverage> {
+        match self {
+            Self::StripeAverage => Some(OrbitAverage::Stripe),
+            Self::TriangleInequalityAverage => Some(OrbitAverage::TriangleInequality),
+            Self::CurvatureAverage => Some(OrbitAverage::Curvature),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    // `palette_offset` is in palette cycles and shifts the colorings that cycle through a palette
+    pub fn escape_time_color(
+        &self,
+        escape_time: &EscapeTime,
+        max_iterations: NonZeroU32,
+        pixel_size: Float,
+        palette_offset: Float,
+    ) -> LinearColor {
         let max_iterations = max_iterations.get();
+        let EscapeTime {
+            iterations: escape_time,
+            distance,
+            trap_distance,
+            trap_color,
+            orbit_average,
+            converged,
+            lyapunov_exponent,
+            ..
+        } = *escape_time;
 
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::unnecessary_cast)]
         match self {
             Self::Histogram => {
                 // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Histogram_coloring
 
-                Color::new(0, 0, ((float(escape_time) / float(max_iterations)) * 255.0) as u8)
+                let s = float(escape_time) / float(max_iterations) + palette_offset;
+                let s = if s > 1.0 { s - 1.0 } else { s };
+
+                LinearColor::from_srgb(0.0, 0.0, s as f32)
             }
             Self::LCH => {
                 // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#LCH_coloring
@@ -189,27 +684,108 @@ impl ColorType {
                     }
                 }
 
-                let s = float(escape_time) / float(max_iterations);
+                let s = float(escape_time) / float(max_iterations) + palette_offset;
                 let v = 1.0 - (PI * s).cos().powi(2);
 
-                Color::new(
-                    (75.0 - (75.0 * v)) as u8,
-                    (28.0 + (75.0 - (75.0 * v))) as u8,
-                    ((360.0 * s).powf(1.5) % 360.0) as u8,
+                LinearColor::from_srgb(
+                    ((75.0 - (75.0 * v)) / 255.0) as f32,
+                    ((28.0 + (75.0 - (75.0 * v))) / 255.0) as f32,
+                    (((360.0 * s).powf(1.5) % 360.0).min(255.0) / 255.0) as f32,
                 )
             }
             Self::OLC => {
                 // https://github.com/OneLoneCoder/Javidx9/blob/54b26051d0fd1491c325ae09f50a7fc3f25030e8/PixelGameEngine/SmallerProjects/OneLoneCoder_PGE_Mandelbrot.cpp#L543C3-L543C3
 
-                let n = float(escape_time);
+                cfg_if! {
+                    if #[cfg(feature = "f32")] {
+                        const TAU: f32 = std::f32::consts::TAU;
+                    } else if #[cfg(feature = "f64")] {
+                        const TAU: f64 = std::f64::consts::TAU;
+                    }
+                }
+
                 let a = 0.1;
+                let n = float(escape_time) + palette_offset * TAU / a;
+
+                LinearColor::from_srgb(
+                    (0.5 * (a * n).sin() + 0.5) as f32,
+                    (0.5 * (a * n + 2.094).sin() + 0.5) as f32,
+                    (0.5 * (a * n + 4.188).sin() + 0.5) as f32,
+                )
+            }
+            Self::Distance => {
+                // https://iquilezles.org/articles/distancefractals/
+
+                let brightness = (0.5 * distance / pixel_size).clamp(0.0, 1.0).powf(0.25);
+                let value = brightness as f32;
+
+                LinearColor::from_srgb(value, value, value)
+            }
+            Self::OrbitTrap => {
+                let glow = (-4.0 * trap_distance).exp();
+
+                LinearColor::from_srgb(glow.sqrt() as f32, glow as f32, glow.powi(3) as f32)
+            }
+            Self::OrbitTrapImage => trap_color.map_or(LinearColor::BLACK, LinearColor::from),
+            Self::StripeAverage | Self::TriangleInequalityAverage | Self::CurvatureAverage => {
+                // https://iquilezles.org/articles/palettes/
 
-                Color::new(
-                    ((0.5 * (a * n).sin() + 0.5) * 255.0) as u8,
-                    ((0.5 * (a * n + 2.094).sin() + 0.5) * 255.0) as u8,
-                    ((0.5 * (a * n + 4.188).sin() + 0.5) * 255.0) as u8,
+                cfg_if! {
+                    if #[cfg(feature = "f32")] {
+                        const TAU: f32 = std::f32::consts::TAU;
+                    } else if #[cfg(feature = "f64")] {
+                        const TAU: f64 = std::f64::consts::TAU;
+                    }
+                }
+
+                let t = orbit_average.unwrap_or(0.0) + palette_offset;
+
+                LinearColor::from_srgb(
+                    (0.5 + 0.5 * (TAU * t).cos()) as f32,
+                    (0.5 + 0.5 * (TAU * (t + 0.1)).cos()) as f32,
+                    (0.5 + 0.5 * (TAU * (t + 0.2)).cos()) as f32,
                 )
             }
+            Self::Lyapunov => {
+                // Stable regions fade from black to yellow, chaotic regions from black to blue
+                let exponent = lyapunov_exponent.unwrap_or(0.0);
+
+                if exponent < 0.0 {
+                    let t = 1.0 - exponent.exp();
+                    LinearColor::from_srgb(t as f32, (t * 220.0 / 255.0) as f32, 0.0)
+                } else {
+                    let t = 1.0 - (-exponent).exp();
+                    LinearColor::from_srgb(0.0, 0.0, t as f32)
+                }
+            }
+            Self::Convergence => {
+                // Orbits converging to a fixed point are orange, escaping orbits are blue
+                cfg_if! {
+                    if #[cfg(feature = "f32")] {
+                        const TAU: f32 = std::f32::consts::TAU;
+                    } else if #[cfg(feature = "f64")] {
+                        const TAU: f64 = std::f64::consts::TAU;
+                    }
+                }
+
+                let value = 0.6 + 0.4 * (0.3 * float(escape_time) + palette_offset * TAU).cos();
+
+                if escape_time == max_iterations {
+                    LinearColor::BLACK
+                } else if converged {
+                    LinearColor::from_srgb(
+                        value as f32,
+                        (value * 160.0 / 255.0) as f32,
+                        (value * 32.0 / 255.0) as f32,
+                    )
+                } else {
+                    LinearColor::from_srgb(
+                        (value * 32.0 / 255.0) as f32,
+                        (value * 96.0 / 255.0) as f32,
+                        value as f32,
+                    )
+                }
+            }
         }
     }
 }
@@ -220,80 +796,286 @@ impl Display for ColorType {
             ColorType::Histogram => write!(f, "Histogram"),
             ColorType::LCH => write!(f, "LCH"),
             ColorType::OLC => write!(f, "OLC"),
+            ColorType::Distance => write!(f, "Distance"),
+            ColorType::OrbitTrap => write!(f, "Orbit trap"),
+            ColorType::OrbitTrapImage => write!(f, "Orbit trap image"),
+            ColorType::StripeAverage => write!(f, "Stripe average"),
+            ColorType::TriangleInequalityAverage => write!(f, "Triangle inequality average"),
+            ColorType::CurvatureAverage => write!(f, "Curvature average"),
+            ColorType::Lyapunov => write!(f, "Lyapunov"),
+            ColorType::Convergence => write!(f, "Convergence"),
         }?;
 
         Ok(())
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::struct_field_names)]
+#[derive(Clone, Debug)]
 pub struct Fractal {
-    fractal_type: FractalType,
+    formula: Arc<dyn IterationFormula>,
     color_type: ColorType,
     camera: Camera,
     max_iterations: NonZeroU32,
+    // `None` uses the Euclidean norm and the radius of the formula
+    bailout: Option<Bailout>,
+    // In palette cycles, between 0 and 1
+    palette_offset: Float,
+    relief: Option<Relief>,
+    orbit_trap: Option<OrbitTrap>,
+    interior_color_type: Option<InteriorColorType>,
 }
 
 impl Fractal {
     #[must_use]
     pub fn new(kind: FractalType, color_type: ColorType, camera: Camera, max_iterations: NonZeroU32) -> Self {
+        Self::from_formula(Arc::new(kind), color_type, camera, max_iterations)
+    }
+
+    #[must_use]
+    pub fn from_formula(
+        formula: Arc<dyn IterationFormula>,
+        color_type: ColorType,
+        camera: Camera,
+        max_iterations: NonZeroU32,
+    ) -> Self {
         Self {
-            fractal_type: kind,
+            formula,
             color_type,
             camera,
             max_iterations,
+            bailout: None,
+            palette_offset: 0.0,
+            relief: None,
+            orbit_trap: None,
+            interior_color_type: None,
         }
     }
-}
 
-impl Fill for Fractal {
-    fn fill(&self, buffer: &mut crate::FrameBuffer) {
+    #[must_use]
+    pub fn formula(&self) -> &dyn IterationFormula {
+        self.formula.as_ref()
+    }
+
+    #[must_use]
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    // `None` for formulas implemented outside of this crate
+    #[must_use]
+    pub fn fractal_type(&self) -> Option<FractalType> {
+        self.formula.as_ref().as_any().downcast_ref::<FractalType>().copied()
+    }
+
+    #[must_use]
+    pub fn bailout(&self) -> Bailout {
+        self.bailout
+            .unwrap_or_else(|| Bailout::of_formula(self.formula.as_ref()))
+    }
+
+    pub fn set_bailout(&mut self, bailout: Option<Bailout>) {
+        self.bailout = bailout;
+    }
+
+    #[must_use]
+    pub fn palette_offset(&self) -> Float {
+        self.palette_offset
+    }
+
+    pub fn set_palette_offset(&mut self, palette_offset: Float) {
+        if palette_offset.is_finite() {
+            self.palette_offset = palette_offset.rem_euclid(1.0);
+        }
+    }
+
+    #[must_use]
+    pub fn relief(&self) -> Option<&Relief> {
+        self.relief.as_ref()
+    }
+
+    pub fn set_relief(&mut self, relief: Option<Relief>) {
+        self.relief = relief;
+    }
+
+    #[must_use]
+    pub fn orbit_trap(&self) -> Option<&OrbitTrap> {
+        self.orbit_trap.as_ref()
+    }
+
+    pub fn set_orbit_trap(&mut self, orbit_trap: Option<OrbitTrap>) {
+        self.orbit_trap = orbit_trap;
+    }
+
+    #[must_use]
+    pub fn interior_color_type(&self) -> Option<InteriorColorType> {
+        self.interior_color_type
+    }
+
+    pub fn set_interior_color_type(&mut self, interior_color_type: Option<InteriorColorType>) {
+        self.interior_color_type = interior_color_type;
+    }
+
+    #[must_use]
+    pub fn escape_times(&self, screen_size: &Vector2<NonZeroU32>) -> Vec<EscapeTime> {
+        #[cfg(feature = "multithread")]
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        let bailout = self.bailout();
+        let index_to_escape_time = |index: u32| {
+            let screen_pos = Vector2::new(index % screen_size.x.get(), index / screen_size.x.get());
+            let world_pos = self.camera.screen_to_world_pos(&screen_pos, screen_size);
+
+            let mut escape_time = self.formula.escape_time(
+                world_pos,
+                self.max_iterations,
+                bailout,
+                self.orbit_trap.as_ref(),
+                self.color_type.orbit_average(),
+            );
+
+            if escape_time.iterations == self.max_iterations.get()
+                && self
+                    .interior_color_type
+                    .is_some_and(|interior_color_type| interior_color_type.needs_orbit())
+            {
+                escape_time.interior = Some(self.formula.interior(world_pos, self.max_iterations));
+            }
+
+            escape_time
+        };
+
+        cfg_if! {
+            if #[cfg(feature = "multithread")] {
+                (0..screen_size.x.get() * screen_size.y.get())
+                    .into_par_iter()
+                    .map(index_to_escape_time)
+                    .collect::<Vec<_>>()
+            } else {
+                (0..screen_size.x.get() * screen_size.y.get())
+                    .map(index_to_escape_time)
+                    .collect::<Vec<_>>()
+            }
+        }
+    }
+
+    fn pixel_color(&self, escape_time: &EscapeTime, pixel_size: Float) -> LinearColor {
+        match self.interior_color_type {
+            Some(interior_color_type) if escape_time.iterations == self.max_iterations.get() => {
+                interior_color_type.interior_color(escape_time.interior.as_ref(), pixel_size)
+            }
+            _ => self
+                .color_type
+                .escape_time_color(escape_time, self.max_iterations, pixel_size, self.palette_offset),
+        }
+    }
+
+    // Full precision render, only on the CPU
+    pub fn fill_hdr(&self, buffer: &mut HdrFrameBuffer) {
+        let screen_size = *buffer.size();
+        #[allow(clippy::cast_possible_truncation)]
+        let pixel_size = |index: usize| {
+            let index = index as u32;
+            let screen_pos = Vector2::new(index % screen_size.x.get(), index / screen_size.x.get());
+
+            self.camera.pixel_size_at(&screen_pos, &screen_size)
+        };
+        let escape_times = self.escape_times(&screen_size);
+
         buffer.data = {
             cfg_if! {
                 if #[cfg(feature = "multithread")] {
-                    use rayon::iter::{IntoParallelIterator, ParallelIterator};
-
-                    (0..buffer.size().x.get() * buffer.size().y.get())
-                        .into_par_iter()
-                        .map(|index| buffer.index_to_pos(index))
-                        .map(|screen_pos|
-                            self.fractal_type.escape_time(
-                                self.camera.screen_to_world_pos(&(screen_pos), buffer.size()),
-                                self.max_iterations,
-                            )
-                        )
-                        .map(|escape_time| self.color_type.escape_time_color(escape_time, self.max_iterations))
+                    use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+                    escape_times
+                        .par_iter()
+                        .enumerate()
+                        .map(|(index, escape_time)| self.pixel_color(escape_time, pixel_size(index)))
+                        .collect::<Vec<_>>()
+                } else {
+                    escape_times
+                        .iter()
+                        .enumerate()
+                        .map(|(index, escape_time)| self.pixel_color(escape_time, pixel_size(index)))
                         .collect::<Vec<_>>()
-                } else if #[cfg(feature = "gpu")] {
-                    use crate::{framebuffer::transform_vec, gpu::do_gpu_compute};
+                }
+            }
+        };
 
+        if let Some(relief) = &self.relief {
+            let heights = escape_times
+                .iter()
+                .enumerate()
+                .map(|(index, escape_time)| relief.height_field().height(escape_time, pixel_size(index)))
+                .collect::<Vec<_>>();
 
-                    let mut io_buffer = (0..buffer.size().x.get() * buffer.size().y.get())
-                        .collect::<Vec<_>>();
+            relief.shade(buffer, &heights);
+        }
 
-                    do_gpu_compute(
-                        &mut io_buffer,
-                        &self.camera,
-                        *buffer.size(),
-                        self.max_iterations,
-                        self.fractal_type,
-                        self.color_type
-                    );
+        for (index, color) in buffer.data.iter_mut().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let index = index as u32;
+            let screen_pos = Vector2::new(index % screen_size.x.get(), index / screen_size.x.get());
 
-                    unsafe { transform_vec::<u32, Color>(io_buffer) }
-                } else {
-                    (0..buffer.size().x.get() * buffer.size().y.get())
-                        .map(|index| buffer.index_to_pos(index))
-                        .map(|screen_pos|
-                            self.fractal_type.escape_time(
-                                self.camera.screen_to_world_pos(&(screen_pos), buffer.size()),
-                                self.max_iterations,
-                            )
-                        )
-                        .map(|escape_time| self.color_type.escape_time_color(escape_time, self.max_iterations))
-                        .collect::<Vec<_>>()
-                }
+            if !self.camera.is_visible(&screen_pos, &screen_size) {
+                *color = LinearColor::BLACK;
             }
         }
     }
+
+    #[cfg(feature = "gpu")]
+    fn gpu_supported(&self) -> bool {
+        self.fractal_type().is_some()
+            && self.palette_offset == 0.0
+            && self.relief.is_none()
+            && self.interior_color_type.is_none()
+            && self.color_type.orbit_average().is_none()
+            && !matches!(self.color_type, ColorType::OrbitTrap | ColorType::OrbitTrapImage)
+    }
+}
+
+impl PartialEq for Fractal {
+    fn eq(&self, other: &Self) -> bool {
+        (Arc::ptr_eq(&self.formula, &other.formula)
+            || self
+                .fractal_type()
+                .is_some_and(|fractal_type| other.fractal_type() == Some(fractal_type)))
+            && self.color_type == other.color_type
+            && self.camera == other.camera
+            && self.max_iterations == other.max_iterations
+            && self.bailout == other.bailout
+            && self.palette_offset == other.palette_offset
+            && self.relief == other.relief
+            && self.orbit_trap == other.orbit_trap
+            && self.interior_color_type == other.interior_color_type
+    }
+}
+
+impl Fill for Fractal {
+    fn fill(&self, buffer: &mut crate::FrameBuffer) {
+        #[cfg(feature = "gpu")]
+        if let Some(fractal_type) = self.fractal_type().filter(|_| self.gpu_supported()) {
+            use crate::{framebuffer::transform_vec, gpu::do_gpu_compute};
+
+            let mut io_buffer = (0..buffer.size().x.get() * buffer.size().y.get()).collect::<Vec<_>>();
+
+            do_gpu_compute(
+                &mut io_buffer,
+                &self.camera,
+                *buffer.size(),
+                self.max_iterations,
+                self.bailout(),
+                &fractal_type,
+                self.color_type,
+            );
+
+            buffer.data = unsafe { transform_vec::<u32, Color>(io_buffer) };
+            return;
+        }
+
+        let mut hdr_buffer = HdrFrameBuffer::new(*buffer.size());
+        self.fill_hdr(&mut hdr_buffer);
+
+        *buffer = hdr_buffer.to_framebuffer(false);
+    }
 }
@@ -0,0 +1,252 @@
+use std::{fmt::Display, num::NonZeroU32};
+
+use cfg_if::cfg_if;
+use cgmath::{Basis3, InnerSpace, Rotation, Vector2, Vector3};
+
+use crate::{camera::rotate, float, Camera, Float};
+
+cfg_if! {
+    if #[cfg(feature = "f32")] {
+        use std::f32::consts::TAU;
+    } else if #[cfg(feature = "f64")] {
+        use std::f64::consts::TAU;
+    }
+}
+
+// Maps the screen to the complex plane, the camera decides which part of it is visible
+pub trait Projection {
+    #[must_use]
+    fn screen_to_world_pos(
+        &self,
+        camera: &Camera,
+        screen_pos: &Vector2<u32>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) -> Vector2<Float>;
+
+    // Inverse of `screen_to_world_pos`, `None` if `world_pos` is not visible
+    #[must_use]
+    fn world_to_screen_pos(
+        &self,
+        camera: &Camera,
+        world_pos: &Vector2<Float>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) -> Option<Vector2<u32>>;
+
+    // Distance in the world between the pixel at `screen_pos` and its neighbours
+    #[must_use]
+    fn pixel_size(&self, camera: &Camera, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> Float;
+
+    // Whether the pixel at `screen_pos` shows the plane at all, the others are left as background
+    #[must_use]
+    fn is_visible(&self, _camera: &Camera, _screen_pos: &Vector2<u32>, _screen_size: &Vector2<NonZeroU32>) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ProjectionType {
+    #[default]
+    Plane,
+    // Log-polar coordinates around the center of the camera, the angle goes along x and the distance shrinks
+    // exponentially down the screen, so every row is zoomed in by the same factor as the previous one
+    // The top row is a circle with the height of the view as its radius, which reaches the corners of wide frames
+    ExponentialMap,
+    // Shows 1 / c, the reciprocal of the position, which turns infinity inside out
+    // https://en.wikipedia.org/wiki/Inversive_geometry#Inversion_in_a_circle
+    Inversion,
+    // The plane wrapped around a sphere by the inverse stereographic projection, 0 at the south pole and infinity at
+    // the north pole, the camera center is the point facing the screen
+    // https://en.wikipedia.org/wiki/Riemann_sphere
+    RiemannSphere,
+}
+
+impl ProjectionType {
+    const NUM_OF_VARIANTS: u8 = 4;
+    // In the plane of the camera, the sphere fills the height of the view
+    const SPHERE_RADIUS: Float = 0.5;
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Plane => 0,
+            Self::ExponentialMap => 1,
+            Self::Inversion => 2,
+            Self::RiemannSphere => 3,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Plane,
+            1 => Self::ExponentialMap,
+            2 => Self::Inversion,
+            3 => Self::RiemannSphere,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self::from_id(self.id() + 1)
+    }
+
+    #[must_use]
+    pub const fn prev(&self) -> Self {
+        Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
+    }
+
+    // Distance from the center of the camera to the top row of the exponential map
+    fn exponential_map_radius(camera: &Camera) -> Float {
+        camera.view_size().y
+    }
+
+    // Turns the south pole towards the screen, then on to the point of the sphere showing the camera center
+    fn sphere_rotation(camera: &Camera) -> Basis3<Float> {
+        Basis3::between_vectors(Vector3::new(0.0, 0.0, -1.0), to_sphere(camera.center_pos))
+    }
+
+    // Position on the unit sphere, with the side facing the screen at negative z
+    fn screen_to_sphere_pos(
+        camera: &Camera,
+        screen_pos: Vector2<u32>,
+        screen_size: Vector2<NonZeroU32>,
+    ) -> Vector3<Float> {
+        let offset = (camera.screen_to_plane_pos(&screen_pos, &screen_size) - camera.center_pos) / Self::SPHERE_RADIUS;
+        // Pixels around the sphere show its rim, `is_visible` hides them
+        let offset = if offset.magnitude2() > 1.0 {
+            offset.normalize()
+        } else {
+            offset
+        };
+
+        Self::sphere_rotation(camera).rotate_vector(Vector3::new(
+            offset.x,
+            offset.y,
+            -(1.0 - offset.magnitude2()).max(0.0).sqrt(),
+        ))
+    }
+}
+
+impl Projection for ProjectionType {
+    fn screen_to_world_pos(
+        &self,
+        camera: &Camera,
+        screen_pos: &Vector2<u32>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) -> Vector2<Float> {
+        match self {
+            Self::Plane => camera.screen_to_plane_pos(screen_pos, screen_size),
+            Self::ExponentialMap => {
+                let width = float(screen_size.x.get());
+                let angle = TAU * float(screen_pos.x) / width;
+                let radius = Self::exponential_map_radius(camera) * (-TAU * float(screen_pos.y) / width).exp();
+
+                camera.rotate_offset(Vector2::new(angle.cos(), angle.sin()) * radius) + camera.center_pos
+            }
+            Self::Inversion => reciprocal(camera.screen_to_plane_pos(screen_pos, screen_size)),
+            Self::RiemannSphere => from_sphere(Self::screen_to_sphere_pos(camera, *screen_pos, *screen_size)),
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn world_to_screen_pos(
+        &self,
+        camera: &Camera,
+        world_pos: &Vector2<Float>,
+        screen_size: &Vector2<NonZeroU32>,
+    ) -> Option<Vector2<u32>> {
+        match self {
+            Self::Plane => camera.plane_to_screen_pos(world_pos, screen_size),
+            Self::ExponentialMap => {
+                let width = float(screen_size.x.get());
+                let offset = rotate(world_pos - camera.center_pos, -camera.rotation);
+
+                let screen_pos = Vector2::new(
+                    offset.y.atan2(offset.x).rem_euclid(TAU) * width / TAU,
+                    (Self::exponential_map_radius(camera) / offset.magnitude()).ln() * width / TAU,
+                );
+
+                if (0.0..width).contains(&screen_pos.x) && (0.0..float(screen_size.y.get())).contains(&screen_pos.y) {
+                    Some(screen_pos.map(|x| x as u32))
+                } else {
+                    None
+                }
+            }
+            Self::Inversion => camera.plane_to_screen_pos(&reciprocal(*world_pos), screen_size),
+            Self::RiemannSphere => {
+                let sphere_pos = Self::sphere_rotation(camera)
+                    .invert()
+                    .rotate_vector(to_sphere(*world_pos));
+
+                // Hidden on the far side of the sphere
+                if sphere_pos.z > 0.0 {
+                    None
+                } else {
+                    camera.plane_to_screen_pos(
+                        &(Vector2::new(sphere_pos.x, sphere_pos.y) * Self::SPHERE_RADIUS + camera.center_pos),
+                        screen_size,
+                    )
+                }
+            }
+        }
+    }
+
+    fn pixel_size(&self, camera: &Camera, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> Float {
+        match self {
+            Self::Plane => camera.pixel_size(screen_size),
+            Self::ExponentialMap => {
+                let width = float(screen_size.x.get());
+
+                Self::exponential_map_radius(camera) * (-TAU * float(screen_pos.y) / width).exp() * TAU / width
+            }
+            Self::Inversion => {
+                camera.pixel_size(screen_size) / camera.screen_to_plane_pos(screen_pos, screen_size).magnitude2()
+            }
+            // Along the rim pixels get stretched even further, like `Camera::pixel_size` the smaller size is used
+            Self::RiemannSphere => {
+                let world_pos = self.screen_to_world_pos(camera, screen_pos, screen_size);
+
+                camera.pixel_size(screen_size) / Self::SPHERE_RADIUS * (1.0 + world_pos.magnitude2()) / 2.0
+            }
+        }
+    }
+
+    fn is_visible(&self, camera: &Camera, screen_pos: &Vector2<u32>, screen_size: &Vector2<NonZeroU32>) -> bool {
+        match self {
+            Self::Plane | Self::ExponentialMap | Self::Inversion => true,
+            Self::RiemannSphere => {
+                (camera.screen_to_plane_pos(screen_pos, screen_size) - camera.center_pos).magnitude()
+                    <= Self::SPHERE_RADIUS
+            }
+        }
+    }
+}
+
+impl Display for ProjectionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectionType::Plane => write!(f, "Plane"),
+            ProjectionType::ExponentialMap => write!(f, "Exponential map"),
+            ProjectionType::Inversion => write!(f, "Inversion (1 / c)"),
+            ProjectionType::RiemannSphere => write!(f, "Riemann sphere"),
+        }?;
+
+        Ok(())
+    }
+}
+
+fn reciprocal(z: Vector2<Float>) -> Vector2<Float> {
+    Vector2::new(z.x, -z.y) / z.magnitude2()
+}
+
+// https://en.wikipedia.org/wiki/Stereographic_projection#First_formulation
+fn to_sphere(z: Vector2<Float>) -> Vector3<Float> {
+    let magnitude2 = z.magnitude2();
+
+    Vector3::new(2.0 * z.x, 2.0 * z.y, magnitude2 - 1.0) / (1.0 + magnitude2)
+}
+
+fn from_sphere(pos: Vector3<Float>) -> Vector2<Float> {
+    Vector2::new(pos.x, pos.y) / (1.0 - pos.z)
+}
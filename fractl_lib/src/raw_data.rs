@@ -0,0 +1,354 @@
+use std::{fmt::Display, io::Write, num::NonZeroU32};
+
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{EscapeTime, Fractal};
+
+// One value per pixel of the uncolored escape time data
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum DataChannel {
+    #[default]
+    Iterations,
+    SmoothIterations,
+    // |z| after the last iteration
+    Magnitude,
+    Distance,
+}
+
+impl DataChannel {
+    const NUM_OF_VARIANTS: u8 = 4;
+    // Sorted by name, the order OpenEXR wants the channels in
+    const EXR_ORDER: [Self; 4] = [
+        Self::Distance,
+        Self::Iterations,
+        Self::Magnitude,
+        Self::SmoothIterations,
+    ];
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Iterations => 0,
+            Self::SmoothIterations => 1,
+            Self::Magnitude => 2,
+            Self::Distance => 3,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Iterations,
+            1 => Self::SmoothIterations,
+            2 => Self::Magnitude,
+            3 => Self::Distance,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self::from_id(self.id() + 1)
+    }
+
+    #[must_use]
+    pub const fn prev(&self) -> Self {
+        Self::from_id(self.id() + Self::NUM_OF_VARIANTS - 1)
+    }
+
+    // Name of the channel in OpenEXR images
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Iterations => "iterations",
+            Self::SmoothIterations => "smooth_iterations",
+            Self::Magnitude => "magnitude",
+            Self::Distance => "distance",
+        }
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::unnecessary_cast
+    )]
+    fn value(self, escape_time: &EscapeTime) -> f32 {
+        match self {
+            Self::Iterations => escape_time.iterations as f32,
+            Self::SmoothIterations => escape_time.smooth_iterations as f32,
+            Self::Magnitude => escape_time.z.magnitude() as f32,
+            Self::Distance => escape_time.distance as f32,
+        }
+    }
+}
+
+impl Display for DataChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataChannel::Iterations => write!(f, "Iterations"),
+            DataChannel::SmoothIterations => write!(f, "Smooth iterations"),
+            DataChannel::Magnitude => write!(f, "Final magnitude"),
+            DataChannel::Distance => write!(f, "Distance estimate"),
+        }?;
+
+        Ok(())
+    }
+}
+
+// Escape time data of a render before it gets colored, for post-processing outside of fractl
+// Stored as 32-bit floats row by row, pixels that the projection does not show are NaN
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawData {
+    size: Vector2<NonZeroU32>,
+    // Indexed by the id of the channel
+    channels: [Vec<f32>; 4],
+}
+
+impl RawData {
+    #[must_use]
+    pub fn new(fractal: &Fractal, screen_size: impl Into<Vector2<NonZeroU32>>) -> Self {
+        let size = screen_size.into();
+        let camera = fractal.camera();
+        let escape_times = fractal.escape_times(&size);
+
+        let channels = [0, 1, 2, 3].map(|id| {
+            let channel = DataChannel::from_id(id);
+
+            escape_times
+                .iter()
+                .enumerate()
+                .map(|(index, escape_time)| {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let index = index as u32;
+                    let screen_pos = Vector2::new(index % size.x.get(), index / size.x.get());
+
+                    if camera.is_visible(&screen_pos, &size) {
+                        channel.value(escape_time)
+                    } else {
+                        f32::NAN
+                    }
+                })
+                .collect()
+        });
+
+        Self { size, channels }
+    }
+
+    #[must_use]
+    pub fn size(&self) -> &Vector2<NonZeroU32> {
+        &self.size
+    }
+
+    #[must_use]
+    pub fn channel(&self, channel: DataChannel) -> &[f32] {
+        &self.channels[channel.id() as usize]
+    }
+
+    // 2D array of shape (height, width), `numpy.load` reads it back
+    // https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write_npy(&self, writer: &mut impl Write, channel: DataChannel) -> Result<(), &'static str> {
+        const WRITE_ERROR: &str = "failed to write the array";
+        const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.size.y, self.size.x
+        );
+        // Padded with spaces and a newline, so the data starts on a multiple of 64 bytes
+        let padded_len = (MAGIC.len() + 2 + header.len() + 1).next_multiple_of(64) - MAGIC.len() - 2;
+        header.extend(std::iter::repeat(' ').take(padded_len - header.len() - 1));
+        header.push('\n');
+
+        writer.write_all(MAGIC).map_err(|_| WRITE_ERROR)?;
+        writer
+            .write_all(
+                &u16::try_from(header.len())
+                    .map_err(|_| "array header is too long")?
+                    .to_le_bytes(),
+            )
+            .map_err(|_| WRITE_ERROR)?;
+        writer.write_all(header.as_bytes()).map_err(|_| WRITE_ERROR)?;
+        writer
+            .write_all(&to_le_bytes(self.channel(channel)))
+            .map_err(|_| WRITE_ERROR)?;
+
+        writer.flush().map_err(|_| WRITE_ERROR)
+    }
+
+    // Uncompressed scanline image with every channel as 32-bit float, named after `DataChannel::name`
+    // https://openexr.com/en/latest/OpenEXRFileLayout.html
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write_exr(&self, writer: &mut impl Write) -> Result<(), &'static str> {
+        const WRITE_ERROR: &str = "failed to write the image";
+        const FLOAT: i32 = 2;
+
+        let (width, height) = (self.size.x.get(), self.size.y.get());
+        let max_x = i32::try_from(width - 1).map_err(|_| "image is too wide")?;
+        let max_y = i32::try_from(height - 1).map_err(|_| "image is too tall")?;
+
+        let mut header = Vec::new();
+        // Magic number and version 2 with no flags, a single part scanline image
+        header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+        let mut channels = Vec::new();
+        for channel in DataChannel::EXR_ORDER {
+            channels.extend_from_slice(channel.name().as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&FLOAT.to_le_bytes());
+            // Not perceptually linear and 3 reserved bytes, then the x and y sampling
+            channels.extend_from_slice(&[0; 4]);
+            channels.extend_from_slice(&1_i32.to_le_bytes());
+            channels.extend_from_slice(&1_i32.to_le_bytes());
+        }
+        channels.push(0);
+
+        let window = [0, 0, max_x, max_y]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        for (name, kind, value) in [
+            ("channels", "chlist", channels.as_slice()),
+            ("compression", "compression", &[0]),
+            ("dataWindow", "box2i", &window),
+            ("displayWindow", "box2i", &window),
+            ("lineOrder", "lineOrder", &[0]),
+            ("pixelAspectRatio", "float", &1.0_f32.to_le_bytes()),
+            ("screenWindowCenter", "v2f", &[0; 8]),
+            ("screenWindowWidth", "float", &1.0_f32.to_le_bytes()),
+        ] {
+            header.extend_from_slice(name.as_bytes());
+            header.push(0);
+            header.extend_from_slice(kind.as_bytes());
+            header.push(0);
+            header.extend_from_slice(
+                &i32::try_from(value.len())
+                    .map_err(|_| "image has too many channels")?
+                    .to_le_bytes(),
+            );
+            header.extend_from_slice(value);
+        }
+        header.push(0);
+
+        // Every line is its own chunk, the offsets point at them from the start of the file
+        let line_len = DataChannel::EXR_ORDER.len() * 4 * width as usize;
+        let first_line = header.len() + 8 * height as usize;
+
+        writer.write_all(&header).map_err(|_| WRITE_ERROR)?;
+        for y in 0..height as usize {
+            writer
+                .write_all(&((first_line + y * (8 + line_len)) as u64).to_le_bytes())
+                .map_err(|_| WRITE_ERROR)?;
+        }
+
+        let line_size = i32::try_from(line_len).map_err(|_| "image is too wide")?;
+        for y in 0..height {
+            let line = (y * width) as usize..((y + 1) * width) as usize;
+
+            writer
+                .write_all(&y.to_le_bytes())
+                .and_then(|()| writer.write_all(&line_size.to_le_bytes()))
+                .map_err(|_| WRITE_ERROR)?;
+
+            for channel in DataChannel::EXR_ORDER {
+                writer
+                    .write_all(&to_le_bytes(&self.channel(channel)[line.clone()]))
+                    .map_err(|_| WRITE_ERROR)?;
+            }
+        }
+
+        writer.flush().map_err(|_| WRITE_ERROR)
+    }
+}
+
+fn to_le_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3 x 2 data where the channel with id `n` holds 10 * n + index
+    #[allow(clippy::cast_precision_loss)]
+    fn raw_data() -> RawData {
+        RawData {
+            size: crate::screen_size(3, 2),
+            channels: [0, 1, 2, 3].map(|id| (0..6).map(|index| (10 * id + index) as f32).collect()),
+        }
+    }
+
+    fn read_u32(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn npy() {
+        let mut npy = Vec::new();
+        raw_data().write_npy(&mut npy, DataChannel::Magnitude).unwrap();
+
+        let header_len = usize::from(u16::from_le_bytes([npy[8], npy[9]]));
+        let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!((10 + header_len) % 64, 0);
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"));
+        assert!(header.ends_with(" \n"));
+        assert_eq!(
+            &npy[10 + header_len..],
+            to_le_bytes(&[20.0, 21.0, 22.0, 23.0, 24.0, 25.0])
+        );
+    }
+
+    #[test]
+    fn exr() {
+        let mut exr = Vec::new();
+        raw_data().write_exr(&mut exr).unwrap();
+
+        assert_eq!(&exr[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+        let channels = b"channels\0chlist\0";
+        let channels_at = exr
+            .windows(channels.len())
+            .position(|window| window == channels)
+            .unwrap()
+            + channels.len();
+        // Every name is followed by its zero terminator and 16 bytes of pixel type, flags and sampling
+        let mut names = Vec::new();
+        let mut at = channels_at + 4;
+        while exr[at] != 0 {
+            let len = exr[at..].iter().position(|byte| *byte == 0).unwrap();
+            names.push(&exr[at..at + len]);
+            assert_eq!(read_u32(&exr, at + len + 1), 2);
+            at += len + 1 + 16;
+        }
+        assert_eq!(
+            names,
+            [&b"distance"[..], b"iterations", b"magnitude", b"smooth_iterations"]
+        );
+
+        // The header ends with a zero byte right before the offset table
+        let line_len = 4 * 4 * 3;
+        let first_line = exr.len() - 2 * (8 + line_len);
+        let offsets_at = first_line - 2 * 8;
+        assert_eq!(exr[offsets_at - 1], 0);
+
+        for y in 0..2 {
+            let offset = u64::from_le_bytes(exr[offsets_at + 8 * y..offsets_at + 8 * (y + 1)].try_into().unwrap());
+            let line = usize::try_from(offset).unwrap();
+
+            assert_eq!(line, first_line + y * (8 + line_len));
+            assert_eq!(read_u32(&exr, line), u32::try_from(y).unwrap());
+            assert_eq!(read_u32(&exr, line + 4), u32::try_from(line_len).unwrap());
+        }
+
+        // Channels of the second line in alphabetical order
+        #[allow(clippy::cast_precision_loss)]
+        let second_line = [3, 0, 2, 1]
+            .iter()
+            .flat_map(|id| (3..6).map(move |index| (10 * id + index) as f32))
+            .collect::<Vec<_>>();
+        assert_eq!(&exr[first_line + 8 + line_len + 8..], to_le_bytes(&second_line));
+    }
+}
@@ -0,0 +1,269 @@
+use std::fmt::Display;
+
+use cfg_if::cfg_if;
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::{framebuffer::LinearColor, EscapeTime, Float, HdrFrameBuffer};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HeightField {
+    #[default]
+    Distance,
+    SmoothIterations,
+}
+
+impl HeightField {
+    const NUM_OF_VARIANTS: u8 = 2;
+
+    #[must_use]
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Distance => 0,
+            Self::SmoothIterations => 1,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: u8) -> Self {
+        match id % Self::NUM_OF_VARIANTS {
+            0 => Self::Distance,
+            1 => Self::SmoothIterations,
+            _ => unreachable!(),
+        }
+    }
+
+    #[must_use]
+    pub fn height(&self, escape_time: &EscapeTime, pixel_size: Float) -> Option<Float> {
+        let height = match self {
+            Self::Distance => -(escape_time.distance / pixel_size).ln(),
+            Self::SmoothIterations => escape_time.smooth_iterations,
+        };
+
+        if escape_time.distance > 0.0 && height.is_finite() {
+            Some(height)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for HeightField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeightField::Distance => write!(f, "Distance"),
+            HeightField::SmoothIterations => write!(f, "Smooth iterations"),
+        }?;
+
+        Ok(())
+    }
+}
+
+// https://en.wikipedia.org/wiki/Phong_reflection_model, using the Blinn-Phong halfway vector
+#[derive(Clone, Debug, PartialEq)]
+pub struct Relief {
+    height_field: HeightField,
+    light_angle: Float,
+    light_height: Float,
+    depth: Float,
+    ambient: Float,
+    specular: Float,
+}
+
+impl Relief {
+    const SHININESS: i32 = 20;
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(
+        height_field: HeightField,
+        light_angle: Float,
+        light_height: Float,
+        depth: Float,
+        ambient: Float,
+        specular: Float,
+    ) -> Result<Self, &'static str> {
+        let mut relief = Self {
+            height_field,
+            ..Self::default()
+        };
+
+        relief.set_light_angle(light_angle)?;
+        relief.set_light_height(light_height)?;
+        relief.set_depth(depth)?;
+        relief.set_ambient(ambient)?;
+        relief.set_specular(specular)?;
+
+        Ok(relief)
+    }
+
+    #[must_use]
+    pub fn height_field(&self) -> HeightField {
+        self.height_field
+    }
+
+    pub fn set_height_field(&mut self, height_field: HeightField) {
+        self.height_field = height_field;
+    }
+
+    #[must_use]
+    pub fn light_angle(&self) -> Float {
+        self.light_angle
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_light_angle(&mut self, light_angle: Float) -> Result<(), &'static str> {
+        if light_angle.is_finite() {
+            self.light_angle = light_angle;
+            Ok(())
+        } else {
+            Err("light angle must be finite")
+        }
+    }
+
+    #[must_use]
+    pub fn light_height(&self) -> Float {
+        self.light_height
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_light_height(&mut self, light_height: Float) -> Result<(), &'static str> {
+        cfg_if! {
+            if #[cfg(feature = "f32")] {
+                const FRAC_PI_2: f32 = std::f32::consts::FRAC_PI_2;
+            } else if #[cfg(feature = "f64")] {
+                const FRAC_PI_2: f64 = std::f64::consts::FRAC_PI_2;
+            }
+        }
+
+        if light_height.is_finite() && (0.0..=FRAC_PI_2).contains(&light_height) {
+            self.light_height = light_height;
+            Ok(())
+        } else {
+            Err("light height must be an angle between 0 and pi / 2 (inclusive)")
+        }
+    }
+
+    #[must_use]
+    pub fn depth(&self) -> Float {
+        self.depth
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_depth(&mut self, depth: Float) -> Result<(), &'static str> {
+        if depth.is_finite() && depth >= 0.0 {
+            self.depth = depth;
+            Ok(())
+        } else {
+            Err("depth must be finite and not negative")
+        }
+    }
+
+    #[must_use]
+    pub fn ambient(&self) -> Float {
+        self.ambient
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_ambient(&mut self, ambient: Float) -> Result<(), &'static str> {
+        if ambient.is_finite() && (0.0..=1.0).contains(&ambient) {
+            self.ambient = ambient;
+            Ok(())
+        } else {
+            Err("ambient must be between 0 and 1.0 (inclusive)")
+        }
+    }
+
+    #[must_use]
+    pub fn specular(&self) -> Float {
+        self.specular
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn set_specular(&mut self, specular: Float) -> Result<(), &'static str> {
+        if specular.is_finite() && (0.0..=1.0).contains(&specular) {
+            self.specular = specular;
+            Ok(())
+        } else {
+            Err("specular must be between 0 and 1.0 (inclusive)")
+        }
+    }
+
+    fn light_direction(&self) -> Vector3<Float> {
+        Vector3::new(
+            self.light_height.cos() * self.light_angle.cos(),
+            self.light_height.cos() * self.light_angle.sin(),
+            self.light_height.sin(),
+        )
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn shade(&self, buffer: &mut HdrFrameBuffer, heights: &[Option<Float>]) {
+        assert_eq!(heights.len(), buffer.data.len());
+
+        let size = buffer.size().map(std::num::NonZeroU32::get);
+        let light_direction = self.light_direction();
+        let halfway = (light_direction + Vector3::new(0.0, 0.0, 1.0)).normalize();
+
+        let shade_pixel = |index: usize, color: LinearColor| -> LinearColor {
+            let Some(height) = heights[index] else {
+                return color;
+            };
+
+            let pos = buffer.index_to_pos(u32::try_from(index).unwrap());
+            let height_at =
+                |x: u32, y: u32| heights[buffer.pos_to_index(Vector2::new(x, y)) as usize].unwrap_or(height);
+
+            let slope = Vector2::new(
+                (height_at((pos.x + 1).min(size.x - 1), pos.y) - height_at(pos.x.saturating_sub(1), pos.y)) / 2.0,
+                (height_at(pos.x, (pos.y + 1).min(size.y - 1)) - height_at(pos.x, pos.y.saturating_sub(1))) / 2.0,
+            );
+            let normal = Vector3::new(-slope.x * self.depth, -slope.y * self.depth, 1.0).normalize();
+
+            let diffuse = normal.dot(light_direction).max(0.0);
+            let specular = self.specular * normal.dot(halfway).max(0.0).powi(Relief::SHININESS);
+            let lightness = self.ambient + (1.0 - self.ambient) * diffuse;
+
+            // Shaded in gamma space so the lighting looks the same as on 8-bit colors, highlights can go above 1.0
+            #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+            let [red, green, blue] = color
+                .to_srgb()
+                .map(|channel| (Float::from(channel) * lightness + specular).max(0.0) as f32);
+
+            LinearColor::from_srgb(red, green, blue).with_alpha(color.alpha)
+        };
+
+        buffer.data = {
+            cfg_if! {
+                if #[cfg(feature = "multithread")] {
+                    use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+                    buffer
+                        .data
+                        .par_iter()
+                        .enumerate()
+                        .map(|(index, color)| shade_pixel(index, *color))
+                        .collect::<Vec<_>>()
+                } else {
+                    buffer
+                        .data
+                        .iter()
+                        .enumerate()
+                        .map(|(index, color)| shade_pixel(index, *color))
+                        .collect::<Vec<_>>()
+                }
+            }
+        };
+    }
+}
+
+impl Default for Relief {
+    fn default() -> Self {
+        Self {
+            height_field: HeightField::default(),
+            light_angle: 0.75,
+            light_height: 0.75,
+            depth: 1.0,
+            ambient: 0.3,
+            specular: 0.3,
+        }
+    }
+}
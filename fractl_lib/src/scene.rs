@@ -0,0 +1,539 @@
+use std::{fmt::Display, num::NonZeroU32};
+
+#[cfg(feature = "png")]
+use std::io::{Read, Write};
+
+use cgmath::Vector2;
+use num_complex::Complex;
+
+use crate::{
+    framebuffer::Color, Bailout, BailoutNorm, Camera, ColorType, Float, Formula, Fractal, FractalType, HeightField,
+    InteriorColorType, LyapunovSequence, OrbitTrap, ProjectionType, Relief,
+};
+#[cfg(feature = "png")]
+use crate::{FrameBuffer, HdrFrameBuffer};
+
+// Everything needed to get back to a view, saved as `key = value` lines
+//
+// fractal = 1 4
+// color = 3
+// center = -0.5 0
+// ...
+//
+// The fractal and the color are stored by their ids, followed by the parameters of the fractal. Image orbit traps
+// are left out, the image itself does not fit in a line
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scene {
+    pub camera: Camera,
+    pub fractal_type: FractalType,
+    pub color_type: ColorType,
+    pub max_iterations: NonZeroU32,
+    pub bailout: Option<Bailout>,
+    // In palette cycles
+    pub palette_offset: Float,
+    pub relief: Option<Relief>,
+    pub orbit_trap: Option<OrbitTrap>,
+    pub interior_color_type: Option<InteriorColorType>,
+}
+
+impl Scene {
+    #[allow(clippy::missing_errors_doc)]
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut camera = Camera {
+            center_pos: Vector2::new(0.0, 0.0),
+            view_size: Vector2::new(1.0, 1.0),
+            zoom: Vector2::new(1.0, 1.0),
+            rotation: 0.0,
+            projection: ProjectionType::default(),
+        };
+        let (mut fractal_type, mut color_type, mut max_iterations, mut bailout) = (None, None, None, None);
+        let mut palette_offset = 0.0;
+        let (mut relief, mut orbit_trap, mut interior_color_type) = (None, None, None);
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or("scene lines must be in the form key = value")?;
+            let value = value.trim();
+
+            match key.trim() {
+                "fractal" => fractal_type = Some(parse_fractal_type(value)?),
+                "palette_offset" => palette_offset = parse_float(value)?,
+                "color" => {
+                    color_type = Some(ColorType::from_id(
+                        value.parse().map_err(|_| "color must be the id of a coloring")?,
+                    ));
+                }
+                "center" => camera.center_pos = parse_vector(value)?,
+                "aspect_ratio" => camera.view_size.x = parse_positive(value)?,
+                "zoom" => {
+                    let zoom = parse_vector(value)?;
+
+                    if zoom.x > 0.0 && zoom.y > 0.0 {
+                        camera.zoom = zoom;
+                    } else {
+                        return Err("zoom must be larger than 0");
+                    }
+                }
+                "rotation" => camera.set_rotation(parse_float(value)?),
+                "projection" => {
+                    camera.projection = ProjectionType::from_id(
+                        value.parse().map_err(|_| "projection must be the id of a projection")?,
+                    );
+                }
+                "max_iterations" => {
+                    max_iterations = Some(value.parse().map_err(|_| "max iterations must be a positive integer")?);
+                }
+                "bailout" if value == "never" => bailout = Some(Bailout::NEVER),
+                "bailout" => {
+                    let (norm, radius) = value.split_once(' ').ok_or("bailout must be a norm id and a radius")?;
+                    let norm = BailoutNorm::from_id(norm.parse().map_err(|_| "bailout norm must be an id")?);
+
+                    bailout = Some(Bailout::new(parse_float(radius)?, norm)?);
+                }
+                "relief" => relief = Some(parse_relief(value)?),
+                "orbit_trap" => orbit_trap = Some(parse_orbit_trap(value)?),
+                "interior" => interior_color_type = Some(parse_interior_color_type(value)?),
+                _ => return Err("unknown scene key"),
+            }
+        }
+
+        Ok(Self {
+            camera,
+            fractal_type: fractal_type.ok_or("scene is missing the fractal")?,
+            color_type: color_type.unwrap_or_default(),
+            max_iterations: max_iterations.ok_or("scene is missing the max iterations")?,
+            bailout,
+            palette_offset,
+            relief,
+            orbit_trap,
+            interior_color_type,
+        })
+    }
+
+    #[must_use]
+    pub fn fractal(&self) -> Fractal {
+        let mut fractal = Fractal::new(
+            self.fractal_type,
+            self.color_type,
+            self.camera.clone(),
+            self.max_iterations,
+        );
+        fractal.set_bailout(self.bailout);
+        fractal.set_palette_offset(self.palette_offset);
+        fractal.set_relief(self.relief.clone());
+        fractal.set_orbit_trap(self.orbit_trap.clone());
+        fractal.set_interior_color_type(self.interior_color_type);
+
+        fractal
+    }
+}
+
+// The scene travels inside the images rendered from it, so a shared PNG is enough to get back to the exact view
+// https://www.w3.org/TR/png/#11textinfo
+#[cfg(feature = "png")]
+impl Scene {
+    // Stored as iTXt, which is UTF-8 unlike tEXt, so custom formulas survive
+    const PNG_KEYWORD: &'static str = "Fractl scene";
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write_png(&self, writer: impl Write, framebuffer: &FrameBuffer) -> Result<(), &'static str> {
+        // Only pays for the alpha channel when something is transparent
+        let (color_type, data) = if framebuffer.is_opaque() {
+            (
+                png::ColorType::Rgb,
+                framebuffer
+                    .data
+                    .iter()
+                    .flat_map(|color| [color.red(), color.green(), color.blue()])
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            (
+                png::ColorType::Rgba,
+                framebuffer
+                    .data
+                    .iter()
+                    .flat_map(|color| [color.red(), color.green(), color.blue(), color.alpha()])
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        self.encode_png(writer, *framebuffer.size(), color_type, png::BitDepth::Eight, &data)
+    }
+
+    // 16 bits per channel, keeps the gradients of a full precision render smooth
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write_png16(&self, writer: impl Write, framebuffer: &HdrFrameBuffer) -> Result<(), &'static str> {
+        let (color_type, data) = if framebuffer.is_opaque() {
+            (png::ColorType::Rgb, framebuffer.rgb16())
+        } else {
+            (png::ColorType::Rgba, framebuffer.rgba16())
+        };
+
+        // PNG stores samples big endian
+        let data = data
+            .iter()
+            .flat_map(|channel| channel.to_be_bytes())
+            .collect::<Vec<_>>();
+
+        self.encode_png(writer, *framebuffer.size(), color_type, png::BitDepth::Sixteen, &data)
+    }
+
+    // Only reads the chunks in front of the pixels, the image itself is not decoded
+    #[allow(clippy::missing_errors_doc)]
+    pub fn read_png(reader: impl Read) -> Result<Self, &'static str> {
+        let reader = png::Decoder::new(reader)
+            .read_info()
+            .map_err(|_| "failed to read the image")?;
+        let info = reader.info();
+
+        let text = match info.utf8_text.iter().find(|chunk| chunk.keyword == Self::PNG_KEYWORD) {
+            Some(chunk) => chunk
+                .get_text()
+                .map_err(|_| "failed to decode the scene in the image")?,
+            None => info
+                .uncompressed_latin1_text
+                .iter()
+                .find(|chunk| chunk.keyword == Self::PNG_KEYWORD)
+                .map(|chunk| chunk.text.clone())
+                .ok_or("image has no scene")?,
+        };
+
+        Self::parse(&text)
+    }
+
+    fn encode_png(
+        &self,
+        writer: impl Write,
+        size: Vector2<NonZeroU32>,
+        color_type: png::ColorType,
+        depth: png::BitDepth,
+        data: &[u8],
+    ) -> Result<(), &'static str> {
+        const WRITE_ERROR: &str = "failed to write the image";
+
+        let mut encoder = png::Encoder::new(writer, size.x.get(), size.y.get());
+        encoder.set_color(color_type);
+        encoder.set_depth(depth);
+
+        encoder
+            .add_text_chunk("Software".to_string(), "Fractl".to_string())
+            .and_then(|()| {
+                encoder.add_itxt_chunk(
+                    "Description".to_string(),
+                    format!("{}, {}", self.fractal_type, self.color_type),
+                )
+            })
+            .and_then(|()| encoder.add_itxt_chunk(Self::PNG_KEYWORD.to_string(), self.to_string()))
+            .map_err(|_| WRITE_ERROR)?;
+
+        let mut writer = encoder.write_header().map_err(|_| WRITE_ERROR)?;
+        writer.write_image_data(data).map_err(|_| WRITE_ERROR)?;
+        writer.finish().map_err(|_| WRITE_ERROR)
+    }
+}
+
+impl Display for Scene {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fractal = {}", self.fractal_type.id())?;
+        match self.fractal_type {
+            FractalType::Multibrot(exponent) => write!(f, " {exponent}")?,
+            FractalType::Lyapunov(sequence) => write!(f, " {sequence}")?,
+            FractalType::Custom(formula) => write!(f, " {formula}")?,
+            FractalType::Phoenix(parametr) | FractalType::Nova(parametr) => {
+                write!(f, " {} {}", parametr.re, parametr.im)?;
+            }
+            FractalType::Mandelbrot | FractalType::MagnetI | FractalType::MagnetII => {}
+        }
+        writeln!(f)?;
+
+        writeln!(f, "color = {}", self.color_type.id())?;
+        if self.palette_offset != 0.0 {
+            writeln!(f, "palette_offset = {}", self.palette_offset)?;
+        }
+        writeln!(f, "center = {} {}", self.camera.center_pos.x, self.camera.center_pos.y)?;
+        writeln!(f, "aspect_ratio = {}", self.camera.view_size.x)?;
+        writeln!(f, "zoom = {} {}", self.camera.zoom.x, self.camera.zoom.y)?;
+        writeln!(f, "rotation = {}", self.camera.rotation)?;
+        if self.camera.projection != ProjectionType::default() {
+            writeln!(f, "projection = {}", self.camera.projection.id())?;
+        }
+        writeln!(f, "max_iterations = {}", self.max_iterations)?;
+
+        match self.bailout {
+            Some(bailout) if bailout.is_never() => writeln!(f, "bailout = never")?,
+            Some(bailout) => writeln!(f, "bailout = {} {}", bailout.norm().id(), bailout.radius())?,
+            None => {}
+        }
+
+        if let Some(relief) = &self.relief {
+            writeln!(
+                f,
+                "relief = {} {} {} {} {} {}",
+                relief.height_field().id(),
+                relief.light_angle(),
+                relief.light_height(),
+                relief.depth(),
+                relief.ambient(),
+                relief.specular()
+            )?;
+        }
+
+        match &self.orbit_trap {
+            Some(OrbitTrap::Point(point)) => writeln!(f, "orbit_trap = point {} {}", point.x, point.y)?,
+            Some(OrbitTrap::Line { point, angle }) => {
+                writeln!(f, "orbit_trap = line {} {} {angle}", point.x, point.y)?;
+            }
+            Some(OrbitTrap::Cross(point)) => writeln!(f, "orbit_trap = cross {} {}", point.x, point.y)?,
+            Some(OrbitTrap::Circle { center, radius }) => {
+                writeln!(f, "orbit_trap = circle {} {} {radius}", center.x, center.y)?;
+            }
+            Some(OrbitTrap::Image { .. }) | None => {}
+        }
+
+        match self.interior_color_type {
+            Some(InteriorColorType::Solid(color)) => writeln!(
+                f,
+                "interior = 0 {} {} {} {}",
+                color.red(),
+                color.green(),
+                color.blue(),
+                color.alpha()
+            )?,
+            Some(interior_color_type) => writeln!(f, "interior = {}", interior_color_type.id())?,
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_float(value: &str) -> Result<Float, &'static str> {
+    value
+        .parse::<Float>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or("invalid number")
+}
+
+fn parse_positive(value: &str) -> Result<Float, &'static str> {
+    Some(parse_float(value)?)
+        .filter(|value| *value > 0.0)
+        .ok_or("number must be larger than 0")
+}
+
+fn parse_vector(value: &str) -> Result<Vector2<Float>, &'static str> {
+    let (x, y) = value.split_once(' ').ok_or("expected two numbers")?;
+
+    Ok(Vector2::new(parse_float(x)?, parse_float(y.trim())?))
+}
+
+fn parse_fractal_type(value: &str) -> Result<FractalType, &'static str> {
+    let (id, parametrs) = value.split_once(' ').unwrap_or((value, ""));
+    let parametrs = parametrs.trim();
+
+    let fractal_type = FractalType::from_id(id.parse().map_err(|_| "fractal must start with the id of a fractal")?);
+
+    Ok(match fractal_type {
+        FractalType::Multibrot(_) => FractalType::Multibrot(parse_float(parametrs)?),
+        FractalType::Lyapunov(_) => FractalType::Lyapunov(LyapunovSequence::new(parametrs)?),
+        FractalType::Custom(_) => FractalType::Custom(Formula::new(parametrs)?),
+        FractalType::Phoenix(_) => {
+            let parametr = parse_vector(parametrs)?;
+            FractalType::Phoenix(Complex::new(parametr.x, parametr.y))
+        }
+        FractalType::Nova(_) => {
+            let parametr = parse_vector(parametrs)?;
+            FractalType::Nova(Complex::new(parametr.x, parametr.y))
+        }
+        FractalType::Mandelbrot | FractalType::MagnetI | FractalType::MagnetII => fractal_type,
+    })
+}
+
+fn parse_floats<const N: usize>(value: &str) -> Result<[Float; N], &'static str> {
+    let mut floats = [0.0; N];
+    let mut values = value.split_whitespace();
+
+    for float in &mut floats {
+        *float = parse_float(values.next().ok_or("too few numbers")?)?;
+    }
+
+    if values.next().is_some() {
+        Err("too many numbers")
+    } else {
+        Ok(floats)
+    }
+}
+
+fn parse_relief(value: &str) -> Result<Relief, &'static str> {
+    let (height_field, parametrs) = value
+        .split_once(' ')
+        .ok_or("relief must be a height field id and 5 numbers")?;
+    let [light_angle, light_height, depth, ambient, specular] = parse_floats(parametrs)?;
+
+    Relief::new(
+        HeightField::from_id(
+            height_field
+                .parse()
+                .map_err(|_| "relief must start with the id of a height field")?,
+        ),
+        light_angle,
+        light_height,
+        depth,
+        ambient,
+        specular,
+    )
+}
+
+fn parse_orbit_trap(value: &str) -> Result<OrbitTrap, &'static str> {
+    let (shape, parametrs) = value
+        .split_once(' ')
+        .ok_or("orbit trap must be a shape and its numbers")?;
+
+    Ok(match shape {
+        "point" => {
+            let [x, y] = parse_floats(parametrs)?;
+            OrbitTrap::Point(Vector2::new(x, y))
+        }
+        "line" => {
+            let [x, y, angle] = parse_floats(parametrs)?;
+            OrbitTrap::Line {
+                point: Vector2::new(x, y),
+                angle,
+            }
+        }
+        "cross" => {
+            let [x, y] = parse_floats(parametrs)?;
+            OrbitTrap::Cross(Vector2::new(x, y))
+        }
+        "circle" => {
+            let [x, y, radius] = parse_floats(parametrs)?;
+            OrbitTrap::Circle {
+                center: Vector2::new(x, y),
+                radius,
+            }
+        }
+        _ => return Err("orbit trap must be a point, line, cross or circle"),
+    })
+}
+
+fn parse_interior_color_type(value: &str) -> Result<InteriorColorType, &'static str> {
+    let (id, parametrs) = value.split_once(' ').unwrap_or((value, ""));
+
+    Ok(
+        match InteriorColorType::from_id(
+            id.parse()
+                .map_err(|_| "interior must start with the id of a coloring")?,
+        ) {
+            InteriorColorType::Solid(_) => {
+                let mut channels = parametrs.split_whitespace().map(|channel| {
+                    channel
+                        .parse::<u8>()
+                        .map_err(|_| "color channels must be between 0 and 255")
+                });
+
+                let mut channel = || {
+                    channels
+                        .next()
+                        .unwrap_or(Err("solid interior must have 4 color channels"))
+                };
+                let color = Color::new_rgba(channel()?, channel()?, channel()?, channel()?);
+
+                if channels.next().is_some() {
+                    return Err("solid interior must have 4 color channels");
+                }
+
+                InteriorColorType::Solid(color)
+            }
+            interior_color_type => interior_color_type,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene() -> Scene {
+        let mut camera = Camera::new(crate::screen_size(16, 9));
+        camera.set_center_pos(Vector2::new(-0.75, 0.125));
+        camera.set_zoom(Vector2::new(1234.5, 1234.5));
+        camera.set_rotation(0.25);
+        camera.set_projection(ProjectionType::from_id(1));
+
+        Scene {
+            camera,
+            fractal_type: FractalType::Custom(Formula::new("conj(z)^2 + c").unwrap()),
+            color_type: ColorType::from_id(3),
+            max_iterations: NonZeroU32::new(500).unwrap(),
+            bailout: Some(Bailout::new(16.0, BailoutNorm::Manhattan).unwrap()),
+            palette_offset: 0.375,
+            relief: Some(Relief::new(HeightField::SmoothIterations, 1.0, 0.5, 2.0, 0.25, 0.75).unwrap()),
+            orbit_trap: Some(OrbitTrap::Line {
+                point: Vector2::new(0.5, -0.5),
+                angle: 0.125,
+            }),
+            interior_color_type: Some(InteriorColorType::Solid(Color::new_rgba(10, 20, 30, 0))),
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let scene = scene();
+        assert_eq!(Scene::parse(&scene.to_string()), Ok(scene));
+
+        let mut scene = Scene {
+            fractal_type: FractalType::Lyapunov(LyapunovSequence::new("AABAB").unwrap()),
+            bailout: Some(Bailout::NEVER),
+            relief: None,
+            orbit_trap: Some(OrbitTrap::Circle {
+                center: Vector2::new(0.0, 1.0),
+                radius: 0.5,
+            }),
+            interior_color_type: Some(InteriorColorType::Period),
+            ..self::scene()
+        };
+        assert_eq!(Scene::parse(&scene.to_string()), Ok(scene.clone()));
+
+        scene.fractal_type = FractalType::Nova(Complex::new(1.0, -0.5));
+        scene.orbit_trap = None;
+        scene.interior_color_type = None;
+        assert_eq!(Scene::parse(&scene.to_string()), Ok(scene));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let text = scene().to_string();
+
+        assert!(Scene::parse("").is_err());
+        assert!(Scene::parse(&text.replace("max_iterations = 500", "max_iterations = 0")).is_err());
+        assert!(Scene::parse(&format!("{text}unknown = 1\n")).is_err());
+        assert!(Scene::parse(&format!("{text}orbit_trap = square 0 0\n")).is_err());
+        assert!(Scene::parse(&format!("{text}interior = 0 1 2 3\n")).is_err());
+        assert!(Scene::parse(&format!("{text}relief = 0 0 0 0 2 0\n")).is_err());
+        assert!(Scene::parse(&format!("{text}bailout = 0 inf\n")).is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_round_trip() {
+        let scene = scene();
+        let mut framebuffer = FrameBuffer::new(crate::screen_size(4, 3));
+        framebuffer.data[0] = Color::TRANSPARENT;
+
+        let mut png = Vec::new();
+        scene.write_png(&mut png, &framebuffer).unwrap();
+        assert_eq!(Scene::read_png(png.as_slice()), Ok(scene.clone()));
+
+        let mut png = Vec::new();
+        scene
+            .write_png16(&mut png, &HdrFrameBuffer::new(*framebuffer.size()))
+            .unwrap();
+        assert_eq!(Scene::read_png(png.as_slice()), Ok(scene));
+    }
+}
@@ -8,6 +8,27 @@ struct Args {
     selected_fractal: u32,
     selected_color: u32,
     multi_exponent: f32,
+    lyapunov_sequence: vec2<u32>,
+    lyapunov_sequence_len: u32,
+    padding: u32,
+    complex_parametr: vec2<f32>,
+    bailout_radius: f32,
+    bailout_norm: u32,
+    rotation: f32,
+    projection: u32,
+}
+
+struct EscapeTime {
+    iterations: u32,
+    distance: f32,
+    lyapunov_exponent: f32,
+    converged: bool,
+}
+
+// Value of an expression together with its derivative, used by custom formulas
+struct Dual {
+    value: vec2<f32>,
+    derivative: vec2<f32>,
 }
 
 @group(0) @binding(0)
@@ -16,22 +37,175 @@ var<storage, read_write> v_indices: array<u32>;
 @group(0) @binding(1)
 var<uniform> args: Args;
 
-fn index_to_world_pos(index: u32) -> vec2<f32> {
+fn index_to_screen_pos(index: u32) -> vec2<u32> {
     let screen_x = index % args.screen_size.x;
     let screen_y = (index - screen_x) / args.screen_size.x;
-    
+
+    return vec2(screen_x, screen_y);
+}
+
+fn rotate(offset: vec2<f32>, angle: f32) -> vec2<f32> {
+    let rotation = vec2(cos(angle), sin(angle));
+
+    return vec2(
+        offset.x * rotation.x - offset.y * rotation.y,
+        offset.x * rotation.y + offset.y * rotation.x,
+    );
+}
+
+fn screen_to_plane_pos(screen_pos: vec2<u32>) -> vec2<f32> {
     let screen_pos_normalized = vec2(
-        (f32(screen_x) / f32(args.screen_size.x)) - 0.5, 
-        (f32(screen_y) / f32(args.screen_size.y)) - 0.5
+        (f32(screen_pos.x) / f32(args.screen_size.x)) - 0.5, 
+        (f32(screen_pos.y) / f32(args.screen_size.y)) - 0.5
     );
 
-    return vec2(
-        ((screen_pos_normalized.x * args.view_size.x) / args.zoom.x) + args.center_pos.x,
-        ((screen_pos_normalized.y * args.view_size.y) / args.zoom.y) + args.center_pos.y,
+    let offset = (screen_pos_normalized * args.view_size) / args.zoom;
+
+    return rotate(offset, args.rotation) + args.center_pos;
+}
+
+// Same as `ProjectionType` in projection.rs
+
+fn exponential_map_radius() -> f32 {
+    return args.view_size.y / args.zoom.y;
+}
+
+fn sphere_radius() -> f32 {
+    return 0.5;
+}
+
+fn to_sphere(z: vec2<f32>) -> vec3<f32> {
+    let magnitude2 = dot(z, z);
+
+    return vec3(2.0 * z.x, 2.0 * z.y, magnitude2 - 1.0) / (1.0 + magnitude2);
+}
+
+// Rotates `v` by the rotation turning the unit vector `a` into `b`
+fn rotate_between(v: vec3<f32>, a: vec3<f32>, b: vec3<f32>) -> vec3<f32> {
+    let axis = cross(a, b);
+    let sin_angle = length(axis);
+    let cos_angle = dot(a, b);
+
+    if sin_angle < 1e-6 {
+        return v;
+    }
+
+    let k = axis / sin_angle;
+
+    return v * cos_angle + cross(k, v) * sin_angle + k * dot(k, v) * (1.0 - cos_angle);
+}
+
+fn screen_to_sphere_pos(screen_pos: vec2<u32>) -> vec3<f32> {
+    var offset = (screen_to_plane_pos(screen_pos) - args.center_pos) / sphere_radius();
+    if dot(offset, offset) > 1.0 {
+        offset = normalize(offset);
+    }
+
+    return rotate_between(
+        vec3(offset, -sqrt(max(1.0 - dot(offset, offset), 0.0))),
+        vec3(0.0, 0.0, -1.0),
+        to_sphere(args.center_pos),
     );
 }
 
-fn mandelbrot_escape_time(world_pos: vec2<f32>) -> u32 {
+fn screen_to_world_pos(screen_pos: vec2<u32>) -> vec2<f32> {
+    let tau = 6.28318;
+
+    var world_pos = vec2(0.0, 0.0);
+    switch args.projection {
+        case 1u: {
+            let width = f32(args.screen_size.x);
+            let angle = tau * f32(screen_pos.x) / width;
+            let radius = exponential_map_radius() * exp(-tau * f32(screen_pos.y) / width);
+
+            world_pos = rotate(vec2(cos(angle), sin(angle)) * radius, args.rotation) + args.center_pos;
+        }
+        case 2u: {
+            let plane_pos = screen_to_plane_pos(screen_pos);
+
+            world_pos = vec2(plane_pos.x, -plane_pos.y) / dot(plane_pos, plane_pos);
+        }
+        case 3u: {
+            let sphere_pos = screen_to_sphere_pos(screen_pos);
+
+            world_pos = sphere_pos.xy / (1.0 - sphere_pos.z);
+        }
+        default: {
+            world_pos = screen_to_plane_pos(screen_pos);
+        }
+    }
+
+    return world_pos;
+}
+
+fn is_visible(screen_pos: vec2<u32>) -> bool {
+    return args.projection != 3u || length(screen_to_plane_pos(screen_pos) - args.center_pos) <= sphere_radius();
+}
+
+fn plane_pixel_size() -> f32 {
+    let view_size = args.view_size / args.zoom;
+
+    return min(view_size.x / f32(args.screen_size.x), view_size.y / f32(args.screen_size.y));
+}
+
+fn pixel_size(screen_pos: vec2<u32>) -> f32 {
+    let tau = 6.28318;
+
+    var size = plane_pixel_size();
+    switch args.projection {
+        case 1u: {
+            let width = f32(args.screen_size.x);
+
+            size = exponential_map_radius() * exp(-tau * f32(screen_pos.y) / width) * tau / width;
+        }
+        case 2u: {
+            let plane_pos = screen_to_plane_pos(screen_pos);
+
+            size = plane_pixel_size() / dot(plane_pos, plane_pos);
+        }
+        case 3u: {
+            let world_pos = screen_to_world_pos(screen_pos);
+
+            size = plane_pixel_size() / sphere_radius() * (1.0 + dot(world_pos, world_pos)) / 2.0;
+        }
+        default: {}
+    }
+
+    return size;
+}
+
+fn is_bounded(z: vec2<f32>) -> bool {
+    switch args.bailout_norm {
+        case 1u: {
+            return abs(z.x) + abs(z.y) <= args.bailout_radius;
+        }
+        case 2u: {
+            return max(abs(z.x), abs(z.y)) <= args.bailout_radius;
+        }
+        case 3u: {
+            return abs(z.x) <= args.bailout_radius;
+        }
+        case 4u: {
+            return abs(z.y) <= args.bailout_radius;
+        }
+        default: {
+            return dot(z, z) <= args.bailout_radius * args.bailout_radius;
+        }
+    }
+}
+
+fn escape_time(iterations: u32, z: vec2<f32>, dz: vec2<f32>) -> EscapeTime {
+    // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Distance_estimates
+
+    var distance: f32 = 0.0;
+    if iterations < args.max_iterations {
+        distance = max(length(z) * log(length(z)) / length(dz), 0.0);
+    }
+
+    return EscapeTime(iterations, distance, 0.0, false);
+}
+
+fn mandelbrot_escape_time(world_pos: vec2<f32>) -> EscapeTime {
     // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Optimized_escape_time_algorithms
 
     var n: u32 = 0u;
@@ -40,18 +214,25 @@ fn mandelbrot_escape_time(world_pos: vec2<f32>) -> u32 {
     var x2: f32 = 0.0;
     var y: f32 = 0.0;
     var y2: f32 = 0.0;
+    var dx: f32 = 0.0;
+    var dy: f32 = 0.0;
 
     let q = pow(world_pos.x - 0.25, 2.0) + pow(world_pos.y, 2.0);
-    let is_in_main_bulb = q * (q + world_pos.x - 0.25) <= 0.25 * pow(world_pos.y, 2.0);
+    let is_in_main_bulb = q * (q + world_pos.x - 0.25) <= 0.25 * pow(world_pos.y, 2.0) && args.bailout_radius >= 2.0;
 
     if is_in_main_bulb {
-        return args.max_iterations;
+        return EscapeTime(args.max_iterations, 0.0, 0.0, false);
     } else {
         loop  {
-            if !((x2 + y2 <= 4.0 )&& (n < args.max_iterations)) {
+            if !(is_bounded(vec2(x, y)) && (n < args.max_iterations)) {
                 break;
             }
 
+            // dz = 2 * z * dz + 1
+            let dx_tmp = 2.0 * (x * dx - y * dy) + 1.0;
+            dy = 2.0 * (x * dy + y * dx);
+            dx = dx_tmp;
+
             y = 2.0 * x * y + world_pos.y;
             x = x2 - y2 + world_pos.x;
 
@@ -62,25 +243,38 @@ fn mandelbrot_escape_time(world_pos: vec2<f32>) -> u32 {
         }
     }
 
-    return n;
+    return escape_time(n, vec2(x, y), vec2(dx, dy));
 }
 
-fn multibrot_escape_time(world_pos: vec2<f32>) -> u32 {
+fn multibrot_escape_time(world_pos: vec2<f32>) -> EscapeTime {
     // https://en.wikipedia.org/wiki/Multibrot_set#Rendering_images
 
     var n: u32 = 0u;
 
     var x: f32 = world_pos.x;
     var y: f32 = world_pos.y;
+    var dx: f32 = 1.0;
+    var dy: f32 = 0.0;
 
     loop {
         let x_y_squared = pow(x, 2.0) + pow(y, 2.0);
-        if !((x_y_squared <= pow(args.multi_exponent, 2.0)) && (n < args.max_iterations)) {
+        if !(is_bounded(vec2(x, y)) && (n < args.max_iterations)) {
             break;
         }
 
+        let atan = atan2(y, x);
+
+        // dz = exponent * z^(exponent - 1) * dz + 1
+        let dz_mult = args.multi_exponent * pow(x_y_squared, (args.multi_exponent - 1.0) / 2.0);
+        let dz_atan = (args.multi_exponent - 1.0) * atan;
+        let dz_mult_x = dz_mult * cos(dz_atan);
+        let dz_mult_y = dz_mult * sin(dz_atan);
+        let dx_tmp = dz_mult_x * dx - dz_mult_y * dy + 1.0;
+        dy = dz_mult_x * dy + dz_mult_y * dx;
+        dx = dx_tmp;
+
         let x_y_squared_exp = pow(x_y_squared, args.multi_exponent / 2.0);
-        let exponent_atan = args.multi_exponent * atan2(y, x);
+        let exponent_atan = args.multi_exponent * atan;
 
         let x_tmp = x_y_squared_exp * cos(exponent_atan) + world_pos.x;
         y = x_y_squared_exp * sin(exponent_atan) + world_pos.y;
@@ -89,7 +283,283 @@ fn multibrot_escape_time(world_pos: vec2<f32>) -> u32 {
         n += 1u;
     }
 
-    return n;
+    return escape_time(n, vec2(x, y), vec2(dx, dy));
+}
+
+fn lyapunov_rate(n: u32, rates: vec2<f32>) -> f32 {
+    let bit = n % args.lyapunov_sequence_len;
+
+    var bits: u32 = args.lyapunov_sequence.x;
+    if bit >= 32u {
+        bits = args.lyapunov_sequence.y;
+    }
+
+    if (bits & (1u << (bit % 32u))) == 0u {
+        return rates.x;
+    } else {
+        return rates.y;
+    }
+}
+
+fn lyapunov_escape_time(world_pos: vec2<f32>) -> EscapeTime {
+    // https://en.wikipedia.org/wiki/Lyapunov_fractal
+
+    let rates = world_pos + 3.0;
+    let warmup_iterations = args.max_iterations / 4u;
+
+    var x: f32 = 0.5;
+    for (var n: u32 = 0u; n < warmup_iterations; n++) {
+        x = lyapunov_rate(n, rates) * x * (1.0 - x);
+    }
+
+    var sum: f32 = 0.0;
+    for (var n: u32 = warmup_iterations; n < warmup_iterations + args.max_iterations; n++) {
+        let rate = lyapunov_rate(n, rates);
+
+        sum += log(abs(rate * (1.0 - 2.0 * x)));
+        x = rate * x * (1.0 - x);
+    }
+
+    var exponent = sum / f32(args.max_iterations);
+    if exponent != exponent {
+        exponent = 3.40282347e+38;
+    }
+
+    return EscapeTime(0u, 0.0, exponent, false);
+}
+
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn complex_div(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
+
+fn complex_exp(a: vec2<f32>) -> vec2<f32> {
+    return exp(a.x) * vec2(cos(a.y), sin(a.y));
+}
+
+fn complex_log(a: vec2<f32>) -> vec2<f32> {
+    return vec2(log(length(a)), atan2(a.y, a.x));
+}
+
+fn complex_sin(a: vec2<f32>) -> vec2<f32> {
+    return vec2(sin(a.x) * cosh(a.y), cos(a.x) * sinh(a.y));
+}
+
+fn complex_cos(a: vec2<f32>) -> vec2<f32> {
+    return vec2(cos(a.x) * cosh(a.y), -sin(a.x) * sinh(a.y));
+}
+
+fn complex_pow(base: vec2<f32>, exponent: vec2<f32>) -> vec2<f32> {
+    if exponent.y == 0.0 && fract(exponent.x) == 0.0 && abs(exponent.x) <= 64.0 {
+        var result = vec2(1.0, 0.0);
+        for (var n: i32 = 0; n < i32(abs(exponent.x)); n++) {
+            result = complex_mul(result, base);
+        }
+
+        if exponent.x < 0.0 {
+            return complex_div(vec2(1.0, 0.0), result);
+        } else {
+            return result;
+        }
+    } else if base.x == 0.0 && base.y == 0.0 {
+        return base;
+    } else {
+        return complex_exp(complex_mul(exponent, complex_log(base)));
+    }
+}
+
+fn dual_add(a: Dual, b: Dual) -> Dual {
+    return Dual(a.value + b.value, a.derivative + b.derivative);
+}
+
+fn dual_sub(a: Dual, b: Dual) -> Dual {
+    return Dual(a.value - b.value, a.derivative - b.derivative);
+}
+
+fn dual_mul(a: Dual, b: Dual) -> Dual {
+    return Dual(
+        complex_mul(a.value, b.value),
+        complex_mul(a.derivative, b.value) + complex_mul(a.value, b.derivative),
+    );
+}
+
+fn dual_div(a: Dual, b: Dual) -> Dual {
+    return Dual(
+        complex_div(a.value, b.value),
+        complex_div(
+            complex_mul(a.derivative, b.value) - complex_mul(a.value, b.derivative),
+            complex_mul(b.value, b.value),
+        ),
+    );
+}
+
+fn dual_pow(a: Dual, b: Dual) -> Dual {
+    let value = complex_pow(a.value, b.value);
+
+    var derivative: vec2<f32>;
+    if b.derivative.x == 0.0 && b.derivative.y == 0.0 {
+        derivative = complex_mul(complex_mul(b.value, complex_pow(a.value, b.value - vec2(1.0, 0.0))), a.derivative);
+    } else {
+        derivative = complex_mul(
+            value,
+            complex_mul(b.derivative, complex_log(a.value)) + complex_div(complex_mul(b.value, a.derivative), a.value),
+        );
+    }
+
+    return Dual(value, derivative);
+}
+
+fn dual_neg(a: Dual) -> Dual {
+    return Dual(-a.value, -a.derivative);
+}
+
+// Component-wise, as in the Burning Ship fractal
+fn dual_abs(a: Dual) -> Dual {
+    let sign = vec2(select(1.0, -1.0, a.value.x < 0.0), select(1.0, -1.0, a.value.y < 0.0));
+
+    return Dual(abs(a.value), sign * a.derivative);
+}
+
+fn dual_conj(a: Dual) -> Dual {
+    return Dual(vec2(a.value.x, -a.value.y), vec2(a.derivative.x, -a.derivative.y));
+}
+
+fn dual_sin(a: Dual) -> Dual {
+    return Dual(complex_sin(a.value), complex_mul(complex_cos(a.value), a.derivative));
+}
+
+fn dual_cos(a: Dual) -> Dual {
+    return Dual(complex_cos(a.value), -complex_mul(complex_sin(a.value), a.derivative));
+}
+
+fn dual_exp(a: Dual) -> Dual {
+    let value = complex_exp(a.value);
+
+    return Dual(value, complex_mul(value, a.derivative));
+}
+
+fn dual_log(a: Dual) -> Dual {
+    return Dual(complex_log(a.value), complex_div(a.derivative, a.value));
+}
+
+// `custom_step` is generated from the selected formula and appended to this shader
+fn custom_escape_time(world_pos: vec2<f32>) -> EscapeTime {
+    var n: u32 = 0u;
+    var z = Dual(world_pos, vec2(1.0, 0.0));
+
+    loop {
+        if !(is_bounded(z.value) && (n < args.max_iterations)) {
+            break;
+        }
+
+        z = custom_step(z, world_pos);
+
+        n += 1u;
+    }
+
+    return escape_time(n, z.value, z.derivative);
+}
+
+fn phoenix_escape_time(world_pos: vec2<f32>) -> EscapeTime {
+    // https://en.wikipedia.org/wiki/Phoenix_fractal
+
+    var n: u32 = 0u;
+
+    var z = vec2(0.0, 0.0);
+    var previous_z = vec2(0.0, 0.0);
+    var dz = vec2(0.0, 0.0);
+    var dz_previous = vec2(0.0, 0.0);
+
+    loop {
+        if !(is_bounded(z) && (n < args.max_iterations)) {
+            break;
+        }
+
+        // dz = 2 * z * dz + 1 + p * dz_previous
+        let next_dz = 2.0 * complex_mul(z, dz) + vec2(1.0, 0.0) + complex_mul(args.complex_parametr, dz_previous);
+        dz_previous = dz;
+        dz = next_dz;
+
+        let next_z = complex_mul(z, z) + world_pos + complex_mul(args.complex_parametr, previous_z);
+        previous_z = z;
+        z = next_z;
+
+        n += 1u;
+    }
+
+    return escape_time(n, z, dz);
+}
+
+fn nova_escape_time(world_pos: vec2<f32>) -> EscapeTime {
+    // https://en.wikipedia.org/wiki/Newton_fractal#Nova_fractal
+
+    var n: u32 = 0u;
+
+    var z = vec2(1.0, 0.0);
+    var is_converged = false;
+
+    loop {
+        if is_converged || !is_bounded(z) || (n >= args.max_iterations) {
+            break;
+        }
+
+        // Relaxed Newton's method for z^3 - 1 with c added
+        let z2 = complex_mul(z, z);
+        let next_z = z - complex_mul(args.complex_parametr, complex_div(complex_mul(z2, z) - vec2(1.0, 0.0), 3.0 * z2))
+            + world_pos;
+        is_converged = length(next_z - z) < 1e-6;
+        z = next_z;
+
+        n += 1u;
+    }
+
+    return EscapeTime(n, 0.0, 0.0, is_converged);
+}
+
+fn magnet_step(z: vec2<f32>, c: vec2<f32>) -> vec2<f32> {
+    let one = vec2(1.0, 0.0);
+
+    var ratio: vec2<f32>;
+    if args.selected_fractal == 6u {
+        ratio = complex_div(complex_mul(z, z) + c - one, 2.0 * z + c - 2.0 * one);
+    } else {
+        let c_1 = c - one;
+        let c_2 = c - 2.0 * one;
+        let c_1_c_2 = complex_mul(c_1, c_2);
+
+        ratio = complex_div(
+            complex_mul(complex_mul(z, z), z) + 3.0 * complex_mul(c_1, z) + c_1_c_2,
+            3.0 * complex_mul(z, z) + 3.0 * complex_mul(c_2, z) + c_1_c_2 + one,
+        );
+    }
+
+    return complex_mul(ratio, ratio);
+}
+
+fn magnet_escape_time(world_pos: vec2<f32>) -> EscapeTime {
+    // https://paulbourke.net/fractals/magnet/
+
+    var n: u32 = 0u;
+
+    var z = vec2(0.0, 0.0);
+    var is_converged = false;
+
+    loop {
+        if is_converged || !is_bounded(z) || (n >= args.max_iterations) {
+            break;
+        }
+
+        let next_z = magnet_step(z, world_pos);
+        is_converged = length(next_z - z) < 1e-6;
+        z = next_z;
+
+        n += 1u;
+    }
+
+    return EscapeTime(n, 0.0, 0.0, is_converged);
 }
 
 fn color_histogram(escape_time: u32) -> u32 {
@@ -126,6 +596,38 @@ fn color_olc(escape_time: u32) -> u32 {
     );
 }
 
+fn color_distance(distance: f32, pixel_size: f32) -> u32 {
+    // https://iquilezles.org/articles/distancefractals/
+
+    let brightness = pow(clamp(0.5 * distance / pixel_size, 0.0, 1.0), 0.25);
+    let value = u32(brightness * 255.0);
+
+    return color(value, value, value);
+}
+
+fn color_lyapunov(exponent: f32) -> u32 {
+    if exponent < 0.0 {
+        let t = 1.0 - exp(exponent);
+        return color(u32(t * 255.0), u32(t * 220.0), 0u);
+    } else {
+        let t = 1.0 - exp(-exponent);
+        return color(0u, 0u, u32(t * 255.0));
+    }
+}
+
+fn color_convergence(escape_time: EscapeTime) -> u32 {
+    // Orbits converging to a fixed point are orange, escaping orbits are blue
+    let value = 0.6 + 0.4 * cos(0.3 * f32(escape_time.iterations));
+
+    if escape_time.iterations == args.max_iterations {
+        return color(0u, 0u, 0u);
+    } else if escape_time.converged {
+        return color(u32(value * 255.0), u32(value * 160.0), u32(value * 32.0));
+    } else {
+        return color(u32(value * 32.0), u32(value * 96.0), u32(value * 255.0));
+    }
+}
+
 fn color(red: u32, green: u32, blue: u32) -> u32 {
     return blue | (green << 8u) | (red << 16u);
 }
@@ -139,9 +641,17 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         return;
     }
 
-    let world_pos = index_to_world_pos(index);
+    let screen_pos = index_to_screen_pos(index);
+
+    // Background around the Riemann sphere
+    if !is_visible(screen_pos) {
+        v_indices[global_id.x] = color(0u, 0u, 0u);
+        return;
+    }
+
+    let world_pos = screen_to_world_pos(screen_pos);
 
-    var escape_time: u32 = 0u;
+    var escape_time: EscapeTime = EscapeTime(0u, 0.0, 0.0, false);
     switch args.selected_fractal {
         case 0u: {
             escape_time = mandelbrot_escape_time(world_pos);
@@ -149,21 +659,45 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         case 1u: {
             escape_time = multibrot_escape_time(world_pos);
         }
+        case 2u: {
+            escape_time = lyapunov_escape_time(world_pos);
+        }
+        case 3u: {
+            escape_time = custom_escape_time(world_pos);
+        }
+        case 4u: {
+            escape_time = phoenix_escape_time(world_pos);
+        }
+        case 5u: {
+            escape_time = nova_escape_time(world_pos);
+        }
+        case 6u, 7u: {
+            escape_time = magnet_escape_time(world_pos);
+        }
         default: {
-            escape_time = u32(-1);
+            escape_time = EscapeTime(u32(-1), 0.0, 0.0, false);
         }
     }
 
     var color: u32 = 0u;
     switch args.selected_color {
         case 0u: {
-            color = color_histogram(escape_time);
+            color = color_histogram(escape_time.iterations);
         }
         case 1u: {
-            color = color_lch(escape_time);
+            color = color_lch(escape_time.iterations);
         }
         case 2u: {
-            color = color_olc(escape_time);
+            color = color_olc(escape_time.iterations);
+        }
+        case 3u: {
+            color = color_distance(escape_time.distance, pixel_size(screen_pos));
+        }
+        case 9u: {
+            color = color_lyapunov(escape_time.lyapunov_exponent);
+        }
+        case 10u: {
+            color = color_convergence(escape_time);
         }
         default: { 
             color = color(255u, 0u, 0u);
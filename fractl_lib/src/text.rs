@@ -2,7 +2,7 @@ use cgmath::Vector2;
 use fontdue::{Font, FontSettings};
 use lazy_static::lazy_static;
 
-use crate::framebuffer::{Color, Draw, FrameBuffer};
+use crate::framebuffer::{BlendMode, Color, Draw, FrameBuffer};
 
 pub struct Label {
     text: String,
@@ -72,8 +72,8 @@ impl Draw for Label {
                                 f32::from(glyph_bitmap[(glyph_pos.y * glyph_size.x + glyph_pos.x) as usize]) / 255.0;
 
                             if glyph_intensity > 0.0 {
-                                buffer[in_buffer_pos] = self.color.scale(glyph_intensity).unwrap()
-                                    + buffer[in_buffer_pos].scale(1.0 - glyph_intensity).unwrap();
+                                let alpha = (f32::from(self.color.alpha()) * glyph_intensity).round() as u8;
+                                buffer.blend_pixel(in_buffer_pos, self.color.with_alpha(alpha), BlendMode::Normal);
                             }
                         }
                     }
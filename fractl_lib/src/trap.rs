@@ -0,0 +1,103 @@
+use std::{fmt::Display, sync::Arc};
+
+use cgmath::Vector2;
+
+use crate::{
+    float,
+    framebuffer::{Color, FrameBuffer},
+    Float,
+};
+
+// https://en.wikipedia.org/wiki/Orbit_trap
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrbitTrap {
+    Point(Vector2<Float>),
+    Line {
+        point: Vector2<Float>,
+        angle: Float,
+    },
+    Cross(Vector2<Float>),
+    Circle {
+        center: Vector2<Float>,
+        radius: Float,
+    },
+    Image {
+        image: Arc<FrameBuffer>,
+        pos: Vector2<Float>,
+        size: Vector2<Float>,
+    },
+}
+
+impl OrbitTrap {
+    #[must_use]
+    pub fn distance(&self, z: Vector2<Float>) -> Float {
+        match self {
+            Self::Point(point) => (z.x - point.x).hypot(z.y - point.y),
+            Self::Line { point, angle } => ((z.x - point.x) * angle.sin() - (z.y - point.y) * angle.cos()).abs(),
+            Self::Cross(point) => (z.x - point.x).abs().min((z.y - point.y).abs()),
+            Self::Circle { center, radius } => ((z.x - center.x).hypot(z.y - center.y) - radius).abs(),
+            Self::Image { pos, size, .. } => (z.x - (pos.x + size.x / 2.0)).hypot(z.y - (pos.y + size.y / 2.0)),
+        }
+    }
+
+    #[must_use]
+    pub fn color(&self, z: Vector2<Float>) -> Option<Color> {
+        if let Self::Image { image, pos, size } = self {
+            let image_pos = Vector2::new((z.x - pos.x) / size.x, (z.y - pos.y) / size.y);
+
+            if (0.0..1.0).contains(&image_pos.x) && (0.0..1.0).contains(&image_pos.y) {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let pixel_pos = Vector2::new(
+                    (image_pos.x * float(image.size().x.get())) as u32,
+                    (image_pos.y * float(image.size().y.get())) as u32,
+                );
+
+                Some(image[pixel_pos])
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for OrbitTrap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrbitTrap::Point(point) => write!(f, "Point ({:?}, {:?})", point.x, point.y),
+            OrbitTrap::Line { point, angle } => write!(f, "Line ({:?}, {:?}, {angle:?})", point.x, point.y),
+            OrbitTrap::Cross(point) => write!(f, "Cross ({:?}, {:?})", point.x, point.y),
+            OrbitTrap::Circle { center, radius } => {
+                write!(f, "Circle ({:?}, {:?}, {radius:?})", center.x, center.y)
+            }
+            OrbitTrap::Image { .. } => write!(f, "Image"),
+        }?;
+
+        Ok(())
+    }
+}
+
+pub(crate) struct OrbitTrapTracker<'a> {
+    orbit_trap: &'a OrbitTrap,
+    pub(crate) distance: Float,
+    pub(crate) color: Option<Color>,
+}
+
+impl<'a> OrbitTrapTracker<'a> {
+    pub(crate) fn new(orbit_trap: &'a OrbitTrap) -> Self {
+        Self {
+            orbit_trap,
+            distance: Float::INFINITY,
+            color: None,
+        }
+    }
+
+    pub(crate) fn update(&mut self, z: Vector2<Float>) {
+        self.distance = self.distance.min(self.orbit_trap.distance(z));
+
+        if self.color.is_none() {
+            self.color = self.orbit_trap.color(z);
+        }
+    }
+}